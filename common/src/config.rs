@@ -43,3 +43,30 @@ pub const MUTATE_ARITH_MAX: u32 = 30;
 pub const RANDOM_LEN_NUM: usize = 30;
 pub const MAX_HAVOC_FLIP_TIMES: usize = 45; // for all bytes
 pub const MAX_SPLICE_TIMES: usize = 45;
+
+// REUSING
+// caps of the label pattern map, 0 means unlimited
+pub const MAX_RECORDS_PER_PATTERN: usize = 2000;
+pub const MAX_PATTERN_RECORDS: usize = 200000;
+// budget of executions for each cond
+pub const REUSING_ITERATIONS: usize = 50;
+pub const MIN_REUSING_ITERATIONS: usize = 10;
+pub const MAX_REUSING_ITERATIONS: usize = 200;
+// success rate at which the adaptive budget reaches its max,
+// reusing starts with a prior of one hit in REUSING_PRIOR_EXEC executions
+pub const REUSING_TARGET_RATE: f64 = 0.05;
+pub const REUSING_PRIOR_EXEC: usize = 20;
+// relocation tries the original offsets of a record shifted by up to this many bytes
+pub const MAX_RELOCATE_SHIFT: i32 = 8;
+// track new crashes and record the values of the conds they reached
+pub const ENABLE_CRASH_TRACK: bool = true;
+// field roles are inferred from at least this many recorded values,
+// and a role has to explain this share of them
+pub const MIN_ROLE_SAMPLES: usize = 16;
+pub const ROLE_MIN_RATIO: f64 = 0.9;
+pub const MAX_ENUM_VALUES: usize = 8;
+// exported dictionary, AFL ignores tokens longer than 128 bytes
+pub const MAX_DICT_TOKENS: usize = 200;
+pub const MAX_DICT_TOKEN_LEN: usize = 128;
+// total size of the input buffers kept in memory
+pub const INPUT_CACHE_BYTES: usize = 64 * 1024 * 1024;
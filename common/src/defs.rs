@@ -5,6 +5,8 @@ pub static ANGORA_BIN_DIR: &str = "ANGORA_BIN_DIR";
 
 // executor.rs
 pub static TRACK_OUTPUT_VAR: &str = "ANGORA_TRACK_OUTPUT";
+// set when tracking a crashing input, so that the log survives the crash
+pub static TRACK_CRASH_VAR: &str = "ANGORA_TRACK_CRASH";
 pub static COND_STMT_ENV_VAR: &str = "ANGORA_COND_STMT_SHM_ID";
 pub static BRANCHES_SHM_ENV_VAR: &str = "ANGORA_BRANCHES_SHM_ID";
 pub static LD_LIBRARY_PATH_VAR: &str = "LD_LIBRARY_PATH";
@@ -30,12 +32,18 @@ pub static ANGORA_DIR_NAME: &str = "angora";
 pub static ANGORA_LOG_FILE: &str = "angora.log";
 pub static COND_QUEUE_FILE: &str = "cond_queue.csv";
 pub static CHART_STAT_FILE: &str = "chart_stat.json";
+pub static LABEL_PATTERN_FILE: &str = "label_patterns.txt";
+pub static LABEL_PATTERN_DB_FILE: &str = "label_patterns.json";
+pub static DICT_FILE: &str = "angora.dict";
+pub static SESSION_DIR: &str = "session";
 
 // tmpfs.rs
 pub static PERSIST_TRACK_FILES: &str = "ANGORA_DISABLE_TMPFS";
 
 pub const SLOW_SPEED: u32 = 888888;
 pub const UNREACHABLE: u64 = std::u64::MAX;
+// `belong` of the conds tracked from crashing inputs, which are not in the queue
+pub const CRASH_BELONG: u32 = u32::MAX;
 
 // ** Cond Type
 // < 0xFF: simple if
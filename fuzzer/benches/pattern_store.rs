@@ -0,0 +1,84 @@
+// Throughput of the label pattern store with a growing number of jobs.
+// A store with a single shard behaves like the former global Mutex.
+// cargo bench -p angora --bench pattern_store
+extern crate angora;
+extern crate angora_common;
+
+use angora::depot::{CondRecord, PatternStore};
+use angora_common::tag::TagSeg;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{sync::Arc, thread, time::Instant};
+
+const NUM_OPS: usize = 200_000;
+const NUM_PATTERNS: u32 = 512;
+// one insert every INSERT_RATIO ops, the rest are lookups as in reusing
+const INSERT_RATIO: usize = 4;
+const THREADS: [usize; 5] = [1, 2, 4, 8, 16];
+const SHARDS: [usize; 2] = [1, 64];
+
+fn pattern(rng: &mut StdRng) -> Vec<u32> {
+    let p = rng.gen_range(0, NUM_PATTERNS);
+    vec![p % 8 + 1, p / 8 + 1]
+}
+
+fn record(pattern: &[u32], rng: &mut StdRng) -> CondRecord {
+    CondRecord {
+        id: rng.gen(),
+        cmpid: rng.gen(),
+        offsets: vec![TagSeg {
+            sign: false,
+            begin: 0,
+            end: pattern.iter().sum(),
+        }],
+        critical_values: pattern.iter().map(|&w| (0..w).map(|_| rng.gen()).collect()).collect(),
+        ..Default::default()
+    }
+}
+
+fn run(store: Arc<PatternStore>, num_threads: usize) -> f64 {
+    let start = Instant::now();
+    let handles: Vec<_> = (0..num_threads)
+        .map(|t| {
+            let store = store.clone();
+            thread::spawn(move || {
+                let mut rng = StdRng::seed_from_u64(t as u64);
+                let mut found = 0;
+                for i in 0..NUM_OPS {
+                    let p = pattern(&mut rng);
+                    if i % INSERT_RATIO == 0 {
+                        // a pattern and its single segments, like create_record_for_offsets
+                        let r = record(&p, &mut rng);
+                        let mut batch = vec![];
+                        for (w, v) in p.iter().zip(r.critical_values.iter()) {
+                            let mut single = r.clone();
+                            single.critical_values = vec![v.clone()];
+                            batch.push((vec![*w], single));
+                        }
+                        batch.push((p, r));
+                        store.insert_batch(batch);
+                    } else {
+                        found += store
+                            .with_records(&p, |records| records.iter().filter(|r| r.outcome.num_tried == 0).count())
+                            .unwrap_or(0);
+                    }
+                }
+                found
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    (NUM_OPS * num_threads) as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    println!("{:>8} {:>8} {:>14}", "shards", "threads", "ops/s");
+    for &num_shards in SHARDS.iter() {
+        for &num_threads in THREADS.iter() {
+            let store = Arc::new(PatternStore::with_shards(num_shards));
+            let throughput = run(store, num_threads);
+            println!("{:>8} {:>8} {:>14.0}", num_shards, num_threads, throughput);
+        }
+    }
+}
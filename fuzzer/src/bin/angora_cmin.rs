@@ -0,0 +1,55 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::cmin_main;
+
+fn main() {
+    let matches = App::new("angora-cmin")
+        .version(crate_version!())
+        .about("Minimize a corpus for a program built with USE_FAST: keep the smallest and fastest inputs that still cover every edge at its hit count bucket.")
+        .arg(Arg::with_name("input_dir")
+             .short("i")
+             .long("input")
+             .value_name("DIR")
+             .help("Sets the directory of inputs to minimize")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("output_dir")
+             .short("o")
+             .long("output")
+             .value_name("DIR")
+             .help("Sets the directory the kept inputs are copied to, it must not exist")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+             .short("M")
+             .long("memory_limit")
+             .value_name("MEM")
+             .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+             .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+             .short("T")
+             .long("time_limit")
+             .value_name("TIME")
+             .help("time limit for programs, default is 1(s)")
+             .takes_value(true))
+       .get_matches();
+
+    cmin_main(
+        matches.value_of("input_dir").unwrap(),
+        matches.value_of("output_dir").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+    );
+}
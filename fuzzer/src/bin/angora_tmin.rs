@@ -0,0 +1,67 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::tmin_main;
+
+fn main() {
+    let matches = App::new("angora-tmin")
+        .version(crate_version!())
+        .about("Minimize a crashing or hanging input for a program built with USE_FAST, by deleting blocks and normalizing bytes while it still ends the same way.")
+        .arg(Arg::with_name("input_file")
+             .short("i")
+             .long("input")
+             .value_name("FILE")
+             .help("Sets the input to minimize")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("output_file")
+             .short("o")
+             .long("output")
+             .value_name("FILE")
+             .help("Sets the file the minimized input is written to")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("track_target")
+             .short("t")
+             .long("track")
+             .value_name("PROM")
+             .help("Sets the target (USE_TRACK) for tracking taints, so that the bytes no cond depends on are deleted first")
+             .takes_value(true))
+        .arg(Arg::with_name("keep_trace")
+             .short("e")
+             .long("keep_trace")
+             .help("Also keep the same edges and hit count buckets, not only the same crash or hang"))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+             .short("M")
+             .long("memory_limit")
+             .value_name("MEM")
+             .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+             .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+             .short("T")
+             .long("time_limit")
+             .value_name("TIME")
+             .help("time limit for programs, default is 1(s)")
+             .takes_value(true))
+       .get_matches();
+
+    tmin_main(
+        matches.value_of("input_file").unwrap(),
+        matches.value_of("output_file").unwrap(),
+        matches.value_of("track_target"),
+        matches.occurrences_of("keep_trace") > 0,
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+    );
+}
@@ -4,7 +4,7 @@ use clap::{App, Arg};
 
 extern crate angora;
 extern crate angora_common;
-use angora::fuzz_main;
+use angora::{fuzz_main, FuzzOpt};
 
 fn main() {
     let matches = App::new("angora-fuzzer")
@@ -20,7 +20,7 @@ fn main() {
              .short("i")
              .long("input")
              .value_name("DIR")
-             .help("Sets the directory of input seeds, use \"-\" to restart with existing output directory")
+             .help("Sets the directory of input seeds, use \"-\" to restart with existing output directory, or to resume it with --resume")
              .takes_value(true)
              .required(true))
         .arg(Arg::with_name("output_dir")
@@ -86,21 +86,103 @@ fn main() {
              .short("E")
              .long("disable_exploitation")
              .help("Disable the fuzzer to mutate sensitive bytes to exploit bugs"))
+        .arg(Arg::with_name("pattern_db")
+             .short("P")
+             .long("pattern_db")
+             .value_name("FILE")
+             .help("Load critical values from a label pattern db (label_patterns.json) saved by a previous run")
+             .takes_value(true))
+        .arg(Arg::with_name("import_patterns")
+             .short("I")
+             .long("import_patterns")
+             .value_name("FILE")
+             .help("Import a read-only label pattern db collected from another target, can be used multiple times")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1))
+        .arg(Arg::with_name("dict")
+             .short("x")
+             .long("dict")
+             .value_name("FILE")
+             .help("Load an AFL-style dictionary file or directory of tokens, can be used multiple times")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1))
+        .arg(Arg::with_name("max_records_per_pattern")
+             .long("max_records_per_pattern")
+             .value_name("NUM")
+             .help("Max number of records kept for each label pattern, default is 2000, set 0 for unlimited")
+             .takes_value(true))
+        .arg(Arg::with_name("max_pattern_records")
+             .long("max_pattern_records")
+             .value_name("NUM")
+             .help("Max number of records kept in the label pattern map, default is 200000, set 0 for unlimited")
+             .takes_value(true))
+        .arg(Arg::with_name("pattern_eviction")
+             .long("pattern_eviction")
+             .value_name("POLICY")
+             .help("Which records to evict when the label pattern map is full? default is productive")
+             .possible_values(&["lru", "productive"]))
+        .arg(Arg::with_name("reusing_budget")
+             .long("reusing_budget")
+             .value_name("BUDGET")
+             .help("How many records to reuse on each cond? fixed tries 50, adaptive scales with the records available and the reusing success rate. default is fixed")
+             .possible_values(&["fixed", "adaptive"]))
+        .arg(Arg::with_name("disable_reusing")
+             .short("R")
+             .long("disable_reusing")
+             .help("Disable the fuzzer to reuse critical values recorded from other conds"))
+        .arg(Arg::with_name("reusing_stage")
+             .long("reusing_stage")
+             .value_name("STAGE")
+             .help("Which reusing stages to run? exact only reuses records of the same pattern, combined only combines single segment records. default is all")
+             .possible_values(&["all", "exact", "combined"]))
+        .arg(Arg::with_name("reusing_order")
+             .long("reusing_order")
+             .value_name("ORDER")
+             .help("Run reusing before or after the gradient search of a cond, default is before")
+             .possible_values(&["before", "after"]))
+        .arg(Arg::with_name("reusing_relocate")
+             .long("reusing_relocate")
+             .help("Also reuse critical values at the offsets they were recorded from, shifted by a few bytes"))
+        .arg(Arg::with_name("schedule")
+             .long("schedule")
+             .value_name("POLICY")
+             .help("How to order the conds in the queue? rr picks the least fuzzed, rarity prefers cmps reached by fewer inputs, gain prefers conds that recently found new paths, cost prefers conds that look cheap to solve. default is rr")
+             .possible_values(&["rr", "rarity", "gain", "cost"]))
+        .arg(Arg::with_name("resume")
+             .long("resume")
+             .help("With -i -, resume the session of the output directory from its last checkpoint, without re-running its inputs"))
        .get_matches();
 
-    fuzz_main(
-        matches.value_of("mode").unwrap_or("llvm"),
-        matches.value_of("input_dir").unwrap(),
-        matches.value_of("output_dir").unwrap(),
-        matches.value_of("track_target").unwrap_or("-"),
-        matches.values_of_lossy("pargs").unwrap(),
-        value_t!(matches, "bind", usize).ok(),
-        value_t!(matches, "thread_jobs", usize).unwrap_or(1),
-        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
-        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
-        matches.value_of("search_method").unwrap_or("gd"),
-        matches.occurrences_of("sync_afl") > 0,
-        matches.occurrences_of("disable_afl_mutation") == 0,
-        matches.occurrences_of("disable_exploitation") == 0,
-    );
+    fuzz_main(FuzzOpt {
+        mode: matches.value_of("mode").unwrap_or("llvm"),
+        in_dir: matches.value_of("input_dir").unwrap(),
+        out_dir: matches.value_of("output_dir").unwrap(),
+        track_target: matches.value_of("track_target").unwrap_or("-"),
+        pargs: matches.values_of_lossy("pargs").unwrap(),
+        bind: value_t!(matches, "bind", usize).ok(),
+        num_jobs: value_t!(matches, "thread_jobs", usize).unwrap_or(1),
+        mem_limit: value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        time_limit: value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+        search_method: matches.value_of("search_method").unwrap_or("gd"),
+        sync_afl: matches.occurrences_of("sync_afl") > 0,
+        enable_afl: matches.occurrences_of("disable_afl_mutation") == 0,
+        enable_exploitation: matches.occurrences_of("disable_exploitation") == 0,
+        pattern_db: matches.value_of("pattern_db"),
+        import_dbs: matches.values_of_lossy("import_patterns").unwrap_or_default(),
+        dicts: matches.values_of_lossy("dict").unwrap_or_default(),
+        max_records_per_pattern: value_t!(matches, "max_records_per_pattern", usize)
+            .unwrap_or(angora_common::config::MAX_RECORDS_PER_PATTERN),
+        max_pattern_records: value_t!(matches, "max_pattern_records", usize)
+            .unwrap_or(angora_common::config::MAX_PATTERN_RECORDS),
+        pattern_eviction: matches.value_of("pattern_eviction").unwrap_or("productive"),
+        enable_reusing: matches.occurrences_of("disable_reusing") == 0,
+        reusing_stage: matches.value_of("reusing_stage").unwrap_or("all"),
+        reusing_order: matches.value_of("reusing_order").unwrap_or("before"),
+        reusing_budget: matches.value_of("reusing_budget").unwrap_or("fixed"),
+        reusing_relocate: matches.occurrences_of("reusing_relocate") > 0,
+        schedule: matches.value_of("schedule").unwrap_or("rr"),
+        resume: matches.occurrences_of("resume") > 0,
+    });
 }
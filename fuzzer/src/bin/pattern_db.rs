@@ -0,0 +1,248 @@
+extern crate angora;
+#[macro_use]
+extern crate clap;
+use angora::depot::{classify_records, hash_values, CondRecord, FieldRole, LabelPattern, PatternDb};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    process,
+};
+
+// Inspect, merge and diff label pattern dbs (label_patterns.json).
+fn main() {
+    let cmpid_arg = || {
+        Arg::with_name("cmpid")
+            .short("c")
+            .long("cmpid")
+            .value_name("CMPID")
+            .help("Only the records of this cmpid")
+            .takes_value(true)
+    };
+    let matches = App::new("pattern-db")
+        .version(crate_version!())
+        .about("Inspect label pattern dbs saved by angora")
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List the patterns by record count")
+                .arg(Arg::with_name("db").required(true))
+                .arg(cmpid_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show the records of a pattern, e.g. 4,2")
+                .arg(Arg::with_name("db").required(true))
+                .arg(Arg::with_name("pattern").required(true))
+                .arg(cmpid_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Merge dbs into one, the outcomes of the same values are summed")
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(Arg::with_name("dbs").required(true).multiple(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Show the critical values found by only one of two runs")
+                .arg(Arg::with_name("a").required(true))
+                .arg(Arg::with_name("b").required(true))
+                .arg(cmpid_arg()),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        ("list", Some(m)) => list(&load(m.value_of("db").unwrap()), cmpid(m)),
+        ("show", Some(m)) => show(
+            &load(m.value_of("db").unwrap()),
+            &parse_pattern(m.value_of("pattern").unwrap()),
+            cmpid(m),
+        ),
+        ("merge", Some(m)) => {
+            let dbs: Vec<PatternDb> = m.values_of("dbs").unwrap().map(load).collect();
+            save(m.value_of("output").unwrap(), &merge(dbs));
+        },
+        ("diff", Some(m)) => diff(
+            &load(m.value_of("a").unwrap()),
+            &load(m.value_of("b").unwrap()),
+            cmpid(m),
+        ),
+        _ => {
+            println!("{}", matches.usage());
+            process::exit(1);
+        },
+    }
+}
+
+fn load(path: &str) -> PatternDb {
+    let file = File::open(path).unwrap_or_else(|e| fail(&format!("Could not open {}: {:?}", path, e)));
+    serde_json::from_reader(BufReader::new(file))
+        .unwrap_or_else(|e| fail(&format!("Could not parse {}: {:?}", path, e)))
+}
+
+fn save(path: &str, db: &PatternDb) {
+    let file = File::create(path).unwrap_or_else(|e| fail(&format!("Could not create {}: {:?}", path, e)));
+    let mut writer = BufWriter::new(file);
+    if let Err(e) = serde_json::to_writer(&mut writer, db).map_err(|e| e.into()).and_then(|_| writer.flush()) {
+        fail(&format!("Could not write {}: {:?}", path, e));
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
+}
+
+fn cmpid(m: &ArgMatches) -> Option<u32> {
+    m.value_of("cmpid").map(|c| c.parse().unwrap_or_else(|_| fail("cmpid should be a number")))
+}
+
+fn parse_pattern(s: &str) -> LabelPattern {
+    s.trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|w| w.trim().parse().unwrap_or_else(|_| fail("pattern should look like 4,2")))
+        .collect()
+}
+
+fn filtered(records: &[CondRecord], cmpid: Option<u32>) -> Vec<&CondRecord> {
+    records.iter().filter(|r| cmpid.is_none_or(|c| r.cmpid == c)).collect()
+}
+
+fn hex(value: &[u8]) -> String {
+    value.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn format_values(values: &[Vec<u8>]) -> String {
+    values.iter().map(|v| hex(v)).collect::<Vec<_>>().join(" ")
+}
+
+fn list(db: &PatternDb, cmpid: Option<u32>) {
+    let mut rows: Vec<(&LabelPattern, usize, usize, usize, FieldRole)> = db
+        .patterns
+        .iter()
+        .map(|(pattern, records)| {
+            let records = filtered(records, cmpid);
+            let tried = records.iter().map(|r| r.outcome.num_tried).sum();
+            let solved = records.iter().map(|r| r.outcome.num_solved).sum();
+            (pattern, records.len(), tried, solved, classify_records(&records))
+        })
+        .filter(|(_, n, _, _, _)| *n > 0)
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!("{:<24} {:>8} {:>8} {:>8}  ROLE", "PATTERN", "RECORDS", "TRIED", "SOLVED");
+    for (pattern, n, tried, solved, role) in rows {
+        println!("{:<24} {:>8} {:>8} {:>8}  {:?}", format!("{:?}", pattern), n, tried, solved, role);
+    }
+}
+
+fn show(db: &PatternDb, pattern: &LabelPattern, cmpid: Option<u32>) {
+    let records = match db.patterns.iter().find(|(p, _)| p == pattern) {
+        Some((_, records)) => filtered(records, cmpid),
+        None => fail(&format!("No pattern {:?} in the db", pattern)),
+    };
+    println!("Pattern: {:?}, records: {}, role: {:?}", pattern, records.len(), classify_records(&records));
+    for (i, r) in records.iter().enumerate() {
+        println!(
+            "[{}] cmpid={}, context={}, op={:#x}, strategy={:?}, solving={}, crash={}, values={}",
+            i,
+            r.cmpid,
+            r.context,
+            r.op,
+            r.strategy,
+            r.solving,
+            r.crash,
+            format_values(&r.critical_values)
+        );
+        println!("    offsets: {:?}, input len: {}, seen: {}", r.offsets, r.input_len, r.num_seen);
+        println!("    outcome: {:?}", r.outcome);
+    }
+}
+
+// Records of the same values in a pattern become one, with their outcomes summed.
+fn merge(dbs: Vec<PatternDb>) -> PatternDb {
+    let mut merged: BTreeMap<LabelPattern, (Vec<CondRecord>, HashMap<u64, usize>)> = BTreeMap::new();
+    for db in dbs {
+        for (pattern, records) in db.patterns {
+            let (list, index) = merged.entry(pattern).or_default();
+            for record in records {
+                let key = hash_values(&record.critical_values);
+                match index.get(&key) {
+                    Some(&i) => {
+                        list[i].outcome.merge(&record.outcome);
+                        list[i].solving |= record.solving;
+                        list[i].crash |= record.crash;
+                        list[i].num_seen += record.num_seen;
+                    },
+                    None => {
+                        index.insert(key, list.len());
+                        list.push(record);
+                    },
+                }
+            }
+        }
+    }
+
+    let mut next_id = 1;
+    let patterns = merged
+        .into_iter()
+        .map(|(pattern, (mut records, _))| {
+            // ids are only unique within one db
+            for r in records.iter_mut() {
+                r.id = next_id;
+                next_id += 1;
+            }
+            (pattern, records)
+        })
+        .collect();
+    PatternDb { patterns }
+}
+
+fn values_of(db: &PatternDb, cmpid: Option<u32>) -> BTreeMap<&LabelPattern, HashSet<&Vec<Vec<u8>>>> {
+    db.patterns
+        .iter()
+        .map(|(pattern, records)| {
+            let values = filtered(records, cmpid).into_iter().map(|r| &r.critical_values).collect();
+            (pattern, values)
+        })
+        .collect()
+}
+
+fn diff(a: &PatternDb, b: &PatternDb, cmpid: Option<u32>) {
+    let values_a = values_of(a, cmpid);
+    let values_b = values_of(b, cmpid);
+    let empty = HashSet::new();
+    let patterns: HashSet<&LabelPattern> = values_a.keys().chain(values_b.keys()).cloned().collect();
+    let mut patterns: Vec<&LabelPattern> = patterns.into_iter().collect();
+    patterns.sort();
+
+    let (mut only_a, mut only_b) = (0, 0);
+    for pattern in patterns {
+        let va = values_a.get(pattern).unwrap_or(&empty);
+        let vb = values_b.get(pattern).unwrap_or(&empty);
+        let mut in_a: Vec<String> = va.difference(vb).map(|v| format_values(v)).collect();
+        let mut in_b: Vec<String> = vb.difference(va).map(|v| format_values(v)).collect();
+        if in_a.is_empty() && in_b.is_empty() {
+            continue;
+        }
+        in_a.sort();
+        in_b.sort();
+        println!("Pattern: {:?}", pattern);
+        for v in &in_a {
+            println!("  < {}", v);
+        }
+        for v in &in_b {
+            println!("  > {}", v);
+        }
+        only_a += in_a.len();
+        only_b += in_b.len();
+    }
+    println!("Only in A: {}, only in B: {}", only_a, only_b);
+}
@@ -1,9 +1,11 @@
-use crate::executor::StatusType;
+use crate::{depot, executor::StatusType};
 use angora_common::{config::BRANCHES_SIZE, shm::SHM};
 #[cfg(feature = "unstable")]
 use std::intrinsics::unlikely;
 use std::{
-    self,
+    self, fs,
+    io::{self, Write},
+    path::Path,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, RwLock,
@@ -65,6 +67,39 @@ impl GlobalBranches {
         let d = self.density.load(Ordering::Relaxed);
         (d * 10000 / BRANCHES_SIZE) as f32 / 100.0
     }
+
+    fn maps(&self) -> [(&'static str, &RwLock<Box<BranchBuf>>); 3] {
+        [
+            ("virgin_branches", &self.virgin_branches),
+            ("tmouts_branches", &self.tmouts_branches),
+            ("crashes_branches", &self.crashes_branches),
+        ]
+    }
+
+    // Write the three maps to `dir` as raw bytes.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        for (name, map) in self.maps().iter() {
+            depot::write_atomically(&dir.join(name), |writer| writer.write_all(&map.read().unwrap()[..]))?;
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, dir: &Path) -> io::Result<()> {
+        for (name, map) in self.maps().iter() {
+            let buf = fs::read(dir.join(name))?;
+            if buf.len() != BRANCHES_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} has {} bytes, expected {}", name, buf.len(), BRANCHES_SIZE),
+                ));
+            }
+            map.write().unwrap().copy_from_slice(&buf);
+        }
+        // the density counts the edges touched in the virgin map
+        let density = self.virgin_branches.read().unwrap().iter().filter(|&&v| v != 255).count();
+        self.density.store(density, Ordering::Relaxed);
+        Ok(())
+    }
 }
 
 pub struct Branches {
@@ -86,7 +121,7 @@ impl Branches {
         self.trace.get_id()
     }
 
-    fn get_path(&self) -> Vec<(usize, u8)> {
+    pub fn get_path(&self) -> Vec<(usize, u8)> {
         let mut path = Vec::<(usize, u8)>::new();
         let buf_plus: &BranchBufPlus = cast!(&*self.trace);
         let buf: &BranchBuf = &*self.trace;
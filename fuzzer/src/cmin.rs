@@ -0,0 +1,140 @@
+// Corpus minimization, like afl-cmin: keep the fewest inputs that still reach every edge
+// with the same hit count bucket (COUNT_LOOKUP).
+use crate::{executor::StatusType, standalone::Standalone};
+use angora_common::config;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+type Tuple = (usize, u8);
+
+// Pick the inputs to keep, by their (cost, trace).
+// Each tuple is covered by its cheapest input, the rarest tuples first, so that
+// inputs chosen for rare edges also cover the common ones.
+pub fn select_inputs(inputs: &[(u64, Vec<Tuple>)]) -> Vec<usize> {
+    let mut best: HashMap<Tuple, usize> = HashMap::new();
+    let mut num_inputs: HashMap<Tuple, usize> = HashMap::new();
+    for (i, (cost, trace)) in inputs.iter().enumerate() {
+        for t in trace {
+            *num_inputs.entry(*t).or_default() += 1;
+            let b = best.entry(*t).or_insert(i);
+            if (*cost, i) < (inputs[*b].0, *b) {
+                *b = i;
+            }
+        }
+    }
+
+    let mut tuples: Vec<(usize, Tuple)> = num_inputs.into_iter().map(|(t, n)| (n, t)).collect();
+    tuples.sort_unstable();
+
+    let mut covered: HashSet<Tuple> = HashSet::new();
+    let mut selected = vec![];
+    for (_, t) in tuples {
+        if covered.contains(&t) {
+            continue;
+        }
+        let i = best[&t];
+        covered.extend(inputs[i].1.iter().copied());
+        selected.push(i);
+    }
+    selected.sort_unstable();
+    selected
+}
+
+fn read_inputs(in_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(in_dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_file()).collect(),
+        Err(e) => {
+            error!("Could not read input directory {:?}: {:?}", in_dir, e);
+            panic!();
+        },
+    };
+    paths.sort();
+    paths
+}
+
+pub fn cmin_main(in_dir: &str, out_dir: &str, pargs: Vec<String>, mem_limit: u64, time_limit: u64) {
+    pretty_env_logger::init();
+
+    let out_dir = PathBuf::from(out_dir);
+    fs::create_dir(&out_dir).expect("Output directory has existed!");
+    let paths = read_inputs(Path::new(in_dir));
+
+    let mut standalone = Standalone::new(None, pargs, mem_limit, time_limit);
+    let executor = &mut standalone.executor;
+
+    // (cost, trace) of the inputs that ran normally, and their paths
+    let mut inputs = vec![];
+    let mut input_paths = vec![];
+    let mut num_skipped = 0;
+    for path in paths {
+        let buf = match fs::read(&path) {
+            Ok(buf) => buf,
+            Err(e) => {
+                warn!("Could not read {:?}: {:?}", path, e);
+                continue;
+            },
+        };
+        if buf.is_empty() || buf.len() > config::MAX_INPUT_LEN {
+            warn!("Skip {:?}, its size {} is out of range", path, buf.len());
+            num_skipped += 1;
+            continue;
+        }
+        let (status, trace, used_us) = executor.run_path(&buf);
+        if status != StatusType::Normal {
+            warn!("Skip {:?}, it ends with {:?}", path, status);
+            num_skipped += 1;
+            continue;
+        }
+        debug!("{:?}: {} edges in {} us", path, trace.len(), used_us);
+        // smaller and faster inputs are cheaper
+        inputs.push((buf.len() as u64 * used_us.max(1) as u64, trace));
+        input_paths.push(path);
+    }
+
+    let selected = select_inputs(&inputs);
+    for &i in &selected {
+        let path = &input_paths[i];
+        fs::copy(path, out_dir.join(path.file_name().unwrap())).unwrap();
+    }
+
+    let num_tuples: HashSet<&Tuple> = inputs.iter().flat_map(|(_, trace)| trace.iter()).collect();
+    println!(
+        "Kept {} of {} inputs ({} skipped), covering {} edge buckets, in {:?}",
+        selected.len(),
+        inputs.len() + num_skipped,
+        num_skipped,
+        num_tuples.len(),
+        out_dir
+    );
+
+    standalone.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_inputs() {
+        assert!(select_inputs(&[]).is_empty());
+
+        let inputs = vec![
+            // covers everything, but is expensive
+            (100, vec![(1, 1), (2, 1), (3, 2)]),
+            (10, vec![(1, 1), (2, 1)]),
+            (10, vec![(3, 2)]),
+            // same edge, other bucket
+            (50, vec![(1, 1), (3, 4)]),
+            // duplicate of the cheap one
+            (10, vec![(1, 1), (2, 1)]),
+        ];
+        assert_eq!(select_inputs(&inputs), vec![1, 2, 3]);
+
+        // the only input reaching the rare edge also covers the common one
+        let inputs = vec![(10, vec![(1, 1)]), (50, vec![(1, 1), (2, 1)])];
+        assert_eq!(select_inputs(&inputs), vec![1]);
+    }
+}
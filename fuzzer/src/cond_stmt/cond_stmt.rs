@@ -2,7 +2,10 @@ use super::CondState;
 use crate::fuzz_type::FuzzType;
 use angora_common::{cond_stmt_base::CondStmtBase, defs, tag::TagSeg};
 use serde_derive::{Deserialize, Serialize};
-use std::hash::{Hash, Hasher};
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct CondStmt {
@@ -18,8 +21,20 @@ pub struct CondStmt {
     pub state: CondState,
     pub num_minimal_optima: usize,
     pub linear: bool,
-
-    pub reusing_record_index: usize,
+    // new paths found in the last round of the cond
+    pub recent_gain: usize,
+
+    // ids of the local records that have been reused on this cond
+    pub reused_records: HashSet<u64>,
+    // (record id, adaptation) keys of the adapted values reused on this cond
+    pub reused_adapted: HashSet<u64>,
+    pub reusing_imported_index: usize,
+    // hashes of the combined segment values reused on this cond
+    pub reused_combinations: HashSet<u64>,
+    // (record id, shift) of the values relocated on this cond
+    pub reused_relocations: HashSet<(u64, i32)>,
+    // reusing has run at least once on this cond
+    pub reusing_attempted: bool,
 }
 
 impl PartialEq for CondStmt {
@@ -53,7 +68,13 @@ impl CondStmt {
             state: CondState::default(),
             num_minimal_optima: 0,
             linear: false,
-            reusing_record_index: 0,
+            recent_gain: 0,
+            reused_records: HashSet::new(),
+            reused_adapted: HashSet::new(),
+            reusing_imported_index: 0,
+            reused_combinations: HashSet::new(),
+            reused_relocations: HashSet::new(),
+            reusing_attempted: false,
         }
     }
 
@@ -1,6 +1,6 @@
 use super::*;
 use crate::{cond_stmt::CondStmt, executor::StatusType};
-use crate::depot::label_pattern_tracker;
+use crate::depot::{label_pattern_tracker, RecordSource};
 use rand;
 use std::{
     collections::HashSet,
@@ -23,16 +23,20 @@ pub struct Depot {
     pub num_hangs: AtomicUsize,
     pub num_crashes: AtomicUsize,
     pub dirs: DepotDir,
+    scheduler: Box<dyn Scheduler>,
+    input_cache: InputCache,
 }
 
 impl Depot {
-    pub fn new(in_dir: PathBuf, out_dir: &Path) -> Self {
+    pub fn new(in_dir: PathBuf, out_dir: &Path, schedule: SchedulePolicy) -> Self {
         Self {
             queue: Mutex::new(PriorityQueue::new()),
             num_inputs: AtomicUsize::new(0),
             num_hangs: AtomicUsize::new(0),
             num_crashes: AtomicUsize::new(0),
             dirs: DepotDir::new(in_dir, out_dir),
+            scheduler: schedule.scheduler(),
+            input_cache: InputCache::new(config::INPUT_CACHE_BYTES),
         }
     }
 
@@ -61,7 +65,9 @@ impl Depot {
     pub fn save(&self, status: StatusType, buf: &Vec<u8>, cmpid: u32) -> usize {
         match status {
             StatusType::Normal => {
-                Self::save_input(&status, buf, &self.num_inputs, cmpid, &self.dirs.inputs_dir)
+                let id = Self::save_input(&status, buf, &self.num_inputs, cmpid, &self.dirs.inputs_dir);
+                self.input_cache.put(id, buf);
+                id
             },
             StatusType::Timeout => {
                 Self::save_input(&status, buf, &self.num_hangs, cmpid, &self.dirs.hangs_dir)
@@ -86,8 +92,15 @@ impl Depot {
     }
 
     pub fn get_input_buf(&self, id: usize) -> Vec<u8> {
-        let path = get_file_name(&self.dirs.inputs_dir, id);
-        read_from_file(&path)
+        self.input_cache.get(id, || {
+            let path = get_file_name(&self.dirs.inputs_dir, id);
+            read_from_file(&path)
+        })
+    }
+
+    // (hits, misses) of the input cache
+    pub fn input_cache_stats(&self) -> (usize, usize) {
+        self.input_cache.stats()
     }
 
     pub fn get_entry(&self) -> Option<(CondStmt, QPriority)> {
@@ -102,14 +115,15 @@ impl Depot {
             .and_then(|x| Some((x.0.clone(), x.1.clone())))
             .and_then(|x| {
                 if !x.1.is_done() {
-                    let q_inc = x.1.inc(x.0.base.op);
+                    let q_inc = self.scheduler.next(&x.0, x.1);
                     q.change_priority(&(x.0), q_inc);
                 }
                 Some(x)
             })
     }
 
-    pub fn add_entries(&self, conds: Vec<CondStmt>) {
+    // `source` is the input that produced the conds.
+    pub fn add_entries(&self, conds: Vec<CondStmt>, source: &RecordSource) {
         let mut q = match self.queue.lock() {
             Ok(guard) => guard,
             Err(poisoned) => {
@@ -119,6 +133,7 @@ impl Depot {
         };
 
         for mut cond in conds {
+            self.scheduler.observe(&cond);
             // DEBUG: Print lb1 and lb2 before adding to depot
             // if cond.base.lb1 > 0 || cond.base.lb2 > 0 {
             //     info!("[DEPOT] Adding CondStmt - cmpid: {}, ctx: {}, ord: {}, lb1: {}, lb2: {}, op: {:#x}, size: {}, arg1: {}, arg2: {}, offsets: {:?}, desirable: {}",
@@ -135,7 +150,8 @@ impl Depot {
                         // If existed one and our new one has two different conditions,
                         // this indicate that it is explored.
                         if v.0.base.condition != cond.base.condition {
-                            label_pattern_tracker::add_cond_to_pattern_map(&cond, self);
+                            // the values of the flipping input solve the cond
+                            label_pattern_tracker::add_cond_to_pattern_map(&cond, source, true);
                             v.0.mark_as_done();
                             q.change_priority(&cond, QPriority::done());
                         } else {
@@ -143,14 +159,14 @@ impl Depot {
                             // If the cond is faster than the older one, we prefer the faster,
                             if config::PREFER_FAST_COND && v.0.speed > cond.speed {
                                 mem::swap(v.0, &mut cond);
-                                let priority = QPriority::init(cond.base.op);
+                                let priority = self.scheduler.init(&cond);
                                 q.change_priority(&cond, priority);
                             }
                         }
                     }
                 } else {
-                    let priority = QPriority::init(cond.base.op);
-                    label_pattern_tracker::add_cond_to_pattern_map(&cond, self);
+                    let priority = self.scheduler.init(&cond);
+                    label_pattern_tracker::add_cond_to_pattern_map(&cond, source, false);
                     q.push(cond, priority);
 
                 }
@@ -159,7 +175,12 @@ impl Depot {
         label_pattern_tracker::print_stats();
     }
 
-    pub fn add_entries_with_filter(&self, conds: Vec<CondStmt>, mutated_offsets: &HashSet<u32>) {
+    pub fn add_entries_with_filter(
+        &self,
+        conds: Vec<CondStmt>,
+        mutated_offsets: &HashSet<u32>,
+        source: &RecordSource,
+    ) {
         let mut q = match self.queue.lock() {
             Ok(guard) => guard,
             Err(poisoned) => {
@@ -169,13 +190,14 @@ impl Depot {
         };
 
         for mut cond in conds {
+            self.scheduler.observe(&cond);
             if cond.is_desirable {
                 if let Some(v) = q.get_mut(&cond) {
                     if !v.0.is_done() {
                         // If existed one and our new one has two different conditions,
                         // this indicate that it is explored.
                         if v.0.base.condition != cond.base.condition {
-                            label_pattern_tracker::add_cond_to_pattern_map_with_filter(&cond, self, mutated_offsets);
+                            label_pattern_tracker::add_cond_to_pattern_map_with_filter(&cond, source, true, mutated_offsets);
                             v.0.mark_as_done();
                             q.change_priority(&cond, QPriority::done());
                         } else {
@@ -183,14 +205,14 @@ impl Depot {
                             // If the cond is faster than the older one, we prefer the faster,
                             if config::PREFER_FAST_COND && v.0.speed > cond.speed {
                                 mem::swap(v.0, &mut cond);
-                                let priority = QPriority::init(cond.base.op);
+                                let priority = self.scheduler.init(&cond);
                                 q.change_priority(&cond, priority);
                             }
                         }
                     }
                 } else {
-                    let priority = QPriority::init(cond.base.op);
-                    label_pattern_tracker::add_cond_to_pattern_map_with_filter(&cond, self, mutated_offsets);
+                    let priority = self.scheduler.init(&cond);
+                    label_pattern_tracker::add_cond_to_pattern_map_with_filter(&cond, source, false, mutated_offsets);
                     q.push(cond, priority);
 
                 }
@@ -199,6 +221,33 @@ impl Depot {
         label_pattern_tracker::print_stats();
     }
 
+    // Conds of the queue with their priorities, for session checkpoints.
+    pub fn queue_entries(&self) -> Vec<(CondStmt, usize)> {
+        let q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        q.iter().map(|(cond, p)| (cond.clone(), p.value())).collect()
+    }
+
+    pub fn restore_queue(&self, entries: Vec<(CondStmt, usize)>) {
+        let mut q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let done = QPriority::done().value();
+        for (cond, p) in entries {
+            let priority = if p == done { QPriority::done() } else { QPriority::new(p) };
+            q.push(cond, priority);
+        }
+    }
+
     pub fn update_entry(&self, cond: CondStmt) {
         let mut q = match self.queue.lock() {
             Ok(guard) => guard,
@@ -214,6 +263,11 @@ impl Depot {
         }
         if cond.is_discarded() {
             q.change_priority(&cond, QPriority::done());
+        } else if let Some(&priority) = q.get_priority(&cond) {
+            if !priority.is_done() {
+                let priority = self.scheduler.update(&cond, priority);
+                q.change_priority(&cond, priority);
+            }
         }
     }
 }
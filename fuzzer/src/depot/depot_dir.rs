@@ -18,9 +18,10 @@ impl DepotDir {
         let hangs_dir = out_dir.join(defs::HANGS_DIR);
         let crashes_dir = out_dir.join(defs::CRASHES_DIR);
 
-        fs::create_dir(&crashes_dir).unwrap();
-        fs::create_dir(&hangs_dir).unwrap();
-        fs::create_dir(&inputs_dir).unwrap();
+        // they already exist when a session is resumed
+        fs::create_dir_all(&crashes_dir).unwrap();
+        fs::create_dir_all(&hangs_dir).unwrap();
+        fs::create_dir_all(&inputs_dir).unwrap();
 
         Self {
             inputs_dir,
@@ -0,0 +1,244 @@
+use super::{
+    file::write_atomically,
+    label_pattern_tracker::{add_token_records, LABEL_PATTERN_MAP},
+    Depot,
+};
+use angora_common::{config, defs};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+    sync::OnceLock,
+};
+
+// Tokens of the user dictionaries, loaded once before fuzzing.
+static DICT_TOKENS: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+
+pub fn dict_tokens() -> &'static [Vec<u8>] {
+    DICT_TOKENS.get().map(|t| &t[..]).unwrap_or(&[])
+}
+
+// A token of the dictionary and how useful it has been.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct TokenScore {
+    reward: f64,
+    count: usize,
+}
+
+fn is_trivial(value: &[u8]) -> bool {
+    value.is_empty() || value.len() > config::MAX_DICT_TOKEN_LEN || value.iter().all(|&b| b == 0)
+}
+
+fn add_token(tokens: &mut HashMap<Vec<u8>, TokenScore>, value: &[u8], reward: f64) {
+    if is_trivial(value) {
+        return;
+    }
+    let score = tokens.entry(value.to_vec()).or_default();
+    score.reward = score.reward.max(reward);
+    score.count += 1;
+}
+
+// Most productive first, then the most frequent.
+fn rank_tokens(tokens: HashMap<Vec<u8>, TokenScore>, n: usize) -> Vec<Vec<u8>> {
+    let mut tokens: Vec<(Vec<u8>, TokenScore)> = tokens.into_iter().collect();
+    tokens.sort_by(|(va, a), (vb, b)| {
+        b.reward
+            .partial_cmp(&a.reward)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.count.cmp(&a.count))
+            .then(va.cmp(vb))
+    });
+    tokens.truncate(n);
+    tokens.into_iter().map(|(v, _)| v).collect()
+}
+
+// Quote a token in the format of AFL's -x dictionaries.
+fn escape_token(value: &[u8]) -> String {
+    let mut s = String::with_capacity(value.len() + 2);
+    s.push('"');
+    for &b in value {
+        match b {
+            0x20..=0x7e if b != b'"' && b != b'\\' => s.push(b as char),
+            _ => s.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    s.push('"');
+    s
+}
+
+// Parse the quoted value of a dictionary line: `name="value"`, `name@level="value"` or `"value"`.
+fn parse_dict_line(line: &str) -> Option<Vec<u8>> {
+    let begin = line.find('"')?;
+    let quoted = &line[begin + 1..];
+    if !quoted.ends_with('"') {
+        return None;
+    }
+    let quoted = &quoted.as_bytes()[..quoted.len() - 1];
+
+    let mut token = vec![];
+    let mut i = 0;
+    while i < quoted.len() {
+        match quoted[i] {
+            b'\\' => {
+                match quoted.get(i + 1)? {
+                    b'\\' => token.push(b'\\'),
+                    b'"' => token.push(b'"'),
+                    b'x' => {
+                        let hex = std::str::from_utf8(quoted.get(i + 2..i + 4)?).ok()?;
+                        token.push(u8::from_str_radix(hex, 16).ok()?);
+                        i += 2;
+                    },
+                    _ => return None,
+                }
+                i += 2;
+            },
+            b => {
+                token.push(b);
+                i += 1;
+            },
+        }
+    }
+    Some(token)
+}
+
+pub fn parse_dict(text: &str) -> Vec<Vec<u8>> {
+    let mut tokens = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_dict_line(line) {
+            Some(token) => tokens.push(token),
+            None => warn!("[Dict] Malformed line {}: {}", i + 1, line),
+        }
+    }
+    tokens
+}
+
+// A dictionary is a file in AFL's format, or a directory with one token per file.
+fn read_dict(path: &Path) -> io::Result<Vec<Vec<u8>>> {
+    if !path.is_dir() {
+        return Ok(parse_dict(&fs::read_to_string(path)?));
+    }
+    let mut tokens = vec![];
+    for entry in path.read_dir()? {
+        let entry_path = entry?.path();
+        if entry_path.is_file() {
+            tokens.push(fs::read(&entry_path)?);
+        }
+    }
+    Ok(tokens)
+}
+
+// Load the user dictionaries for havoc and exploitation, and store their tokens
+// in the pattern map for reusing. Returns the number of tokens.
+pub fn load_dicts(paths: &[String]) -> io::Result<usize> {
+    let mut tokens: Vec<Vec<u8>> = vec![];
+    for path in paths {
+        for token in read_dict(Path::new(path))? {
+            if token.is_empty() || token.len() > config::MAX_DICT_TOKEN_LEN {
+                warn!("[Dict] Skip a token of {} bytes in {:?}", token.len(), path);
+            } else if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+    }
+
+    let num_records = add_token_records(&tokens);
+    info!("[Dict] Loaded {} tokens, {} new records", tokens.len(), num_records);
+    let num_tokens = tokens.len();
+    if DICT_TOKENS.set(tokens).is_err() {
+        warn!("The dictionaries can only be loaded once");
+    }
+    Ok(num_tokens)
+}
+
+// Critical values of the pattern map and magic bytes of the queued conds.
+fn collect_tokens(depot: &Depot) -> HashMap<Vec<u8>, TokenScore> {
+    let mut tokens = HashMap::new();
+    for (_, records) in LABEL_PATTERN_MAP.snapshot() {
+        for record in records {
+            // values that flipped their cond are worth one more solve
+            let reward = record.outcome.mean_reward() + if record.solving { 1.0 } else { 0.0 };
+            for value in &record.critical_values {
+                add_token(&mut tokens, value, reward);
+            }
+        }
+    }
+
+    let q = match depot.queue.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+            poisoned.into_inner()
+        },
+    };
+    for (cond, _) in q.iter() {
+        // `variables` starts with the constant operand, see fparser,
+        // until the search of an integer cond overwrites it.
+        let size = cond.base.size as usize;
+        let intact = cond.base.op == defs::COND_FN_OP
+            || (cond.base.op <= defs::COND_MAX_EXPLOIT_OP && cond.fuzz_times == 0);
+        if intact && size > 0 && cond.variables.len() >= size {
+            add_token(&mut tokens, &cond.variables[..size], 0.0);
+        }
+    }
+    tokens
+}
+
+// Write the tokens to a dictionary for AFL's -x or libFuzzer's -dict. Returns the number of tokens.
+pub fn export_dict(path: &Path, depot: &Depot) -> io::Result<usize> {
+    let tokens = rank_tokens(collect_tokens(depot), config::MAX_DICT_TOKENS);
+
+    write_atomically(path, |file| {
+        writeln!(file, "# Angora critical values and magic bytes, most productive first")?;
+        for (i, token) in tokens.iter().enumerate() {
+            writeln!(file, "angora_{:04}={}", i, escape_token(token))?;
+        }
+        Ok(())
+    })?;
+
+    debug!("[Dict] Exported {} tokens to {:?}", tokens.len(), path);
+    Ok(tokens.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_tokens() {
+        let mut tokens = HashMap::new();
+        add_token(&mut tokens, b"IHDR", 0.5);
+        add_token(&mut tokens, &[0, 0, 0, 0], 2.0);
+        add_token(&mut tokens, b"GIF8", 0.0);
+        add_token(&mut tokens, b"GIF8", 0.0);
+        add_token(&mut tokens, b"ab", 0.0);
+        add_token(&mut tokens, b"IHDR", 0.0);
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(
+            rank_tokens(tokens, 2),
+            vec![b"IHDR".to_vec(), b"GIF8".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_parse_dict() {
+        let text = "# comment\n\nkw1=\"GIF89a\"\nkw2@1=\"\\x89PNG\"\n\"a\\\"b\\\\\"\nbad=\"\\x8\"\nbroken\n";
+        assert_eq!(
+            parse_dict(text),
+            vec![b"GIF89a".to_vec(), b"\x89PNG".to_vec(), b"a\"b\\".to_vec()]
+        );
+        // exported dictionaries can be loaded back
+        let token = [0x89, b'"', 0, b'\\'];
+        assert_eq!(parse_dict_line(&escape_token(&token)), Some(token.to_vec()));
+    }
+
+    #[test]
+    fn test_escape_token() {
+        assert_eq!(escape_token(b"GIF89a"), "\"GIF89a\"");
+        assert_eq!(escape_token(&[0x89, b'P', b'"', b'\\', 0]), "\"\\x89P\\x22\\x5c\\x00\"");
+    }
+}
@@ -0,0 +1,278 @@
+use super::label_pattern_tracker::{CondRecord, LabelPattern, LABEL_PATTERN_MAP};
+use angora_common::config;
+use std::collections::{HashMap, HashSet};
+
+// What a field of the input is, inferred from the values recorded for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldRole {
+    #[default]
+    Unknown,
+    // always the same value
+    Magic,
+    // the input length plus `delta`
+    Length { delta: i64, big_endian: bool },
+    // a different value for nearly every input, e.g. a checksum or a hash
+    Checksum,
+    // one of a few values
+    Enum,
+}
+
+impl FieldRole {
+    // Whether values recorded from other inputs are worth copying.
+    pub fn copies_values(&self) -> bool {
+        !matches!(self, FieldRole::Length { .. } | FieldRole::Checksum)
+    }
+
+    // Whether values out of the recorded set, resized or re-split, are worth trying.
+    pub fn adapts_values(&self) -> bool {
+        *self == FieldRole::Unknown
+    }
+}
+
+// A length field found in the inputs, at the offset where it was last read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthField {
+    pub begin: usize,
+    pub width: usize,
+    pub delta: i64,
+    pub big_endian: bool,
+}
+
+fn num_samples(records: &[&CondRecord]) -> usize {
+    // records of old dbs have not been counted
+    records.iter().map(|r| r.num_seen.max(1)).sum()
+}
+
+fn read_int(value: &[u8], big_endian: bool) -> Option<u64> {
+    if value.is_empty() || value.len() > 8 {
+        return None;
+    }
+    let fold = |v: u64, b: &u8| (v << 8) | *b as u64;
+    Some(if big_endian {
+        value.iter().fold(0, fold)
+    } else {
+        value.iter().rev().fold(0, fold)
+    })
+}
+
+// The value of a length field for an input of `len` bytes.
+pub fn encode_length(len: usize, delta: i64, big_endian: bool, width: usize) -> Option<Vec<u8>> {
+    let value = len as i64 + delta;
+    if value < 0 || width == 0 || width > 8 || (width < 8 && value >= 1i64 << (width * 8)) {
+        return None;
+    }
+    let mut bytes = (value as u64).to_le_bytes()[..width].to_vec();
+    if big_endian {
+        bytes.reverse();
+    }
+    Some(bytes)
+}
+
+// The distance from the input length shared by most values, if they were taken from inputs of different lengths.
+fn length_delta(records: &[&CondRecord], big_endian: bool) -> Option<i64> {
+    let mut deltas: HashMap<i64, (usize, HashSet<u32>)> = HashMap::new();
+    for r in records {
+        if r.input_len == 0 || r.critical_values.len() != 1 {
+            continue;
+        }
+        let value = match read_int(&r.critical_values[0], big_endian) {
+            Some(value) => value as i128,
+            None => continue,
+        };
+        let delta = value - r.input_len as i128;
+        if delta.abs() > config::MAX_INPUT_LEN as i128 {
+            continue;
+        }
+        let (weight, lens) = deltas.entry(delta as i64).or_default();
+        *weight += r.num_seen.max(1);
+        lens.insert(r.input_len);
+    }
+
+    let min_weight = config::ROLE_MIN_RATIO * num_samples(records) as f64;
+    deltas
+        .into_iter()
+        .filter(|(_, (weight, lens))| lens.len() >= 2 && *weight as f64 >= min_weight)
+        .max_by_key(|(delta, (weight, _))| (*weight, -delta))
+        .map(|(delta, _)| delta)
+}
+
+pub fn classify_records(records: &[&CondRecord]) -> FieldRole {
+    let total = num_samples(records);
+    if records.is_empty() || total < config::MIN_ROLE_SAMPLES {
+        return FieldRole::Unknown;
+    }
+    if records.len() == 1 {
+        return FieldRole::Magic;
+    }
+    for big_endian in [false, true] {
+        if let Some(delta) = length_delta(records, big_endian) {
+            return FieldRole::Length { delta, big_endian };
+        }
+    }
+    if records.len() <= config::MAX_ENUM_VALUES {
+        return FieldRole::Enum;
+    }
+    let width: usize = records[0].critical_values.iter().map(|v| v.len()).sum();
+    if width >= 2 && records.len() as f64 >= config::ROLE_MIN_RATIO * total as f64 {
+        return FieldRole::Checksum;
+    }
+    FieldRole::Unknown
+}
+
+// A pattern mixes the fields of every cond of its widths, e.g. [4] holds the values of every
+// 4 byte comparison, so only the records of the cond's own comparison are classified.
+fn cmp_role(records: &[CondRecord], cmpid: u32) -> FieldRole {
+    let same_cmp: Vec<&CondRecord> = records.iter().filter(|r| r.cmpid == cmpid).collect();
+    classify_records(&same_cmp)
+}
+
+pub fn pattern_role(pattern: &LabelPattern, cmpid: u32) -> FieldRole {
+    LABEL_PATTERN_MAP
+        .with_records(pattern, |records| cmp_role(records, cmpid))
+        .unwrap_or_default()
+}
+
+// Length fields of the integer patterns, one per comparison that read them.
+pub fn length_fields() -> Vec<LengthField> {
+    let mut fields: Vec<LengthField> = vec![];
+    for width in [1, 2, 4, 8] {
+        LABEL_PATTERN_MAP.with_records(&vec![width as u32], |records| {
+            let mut by_cmp: HashMap<u32, Vec<&CondRecord>> = HashMap::new();
+            for r in records {
+                by_cmp.entry(r.cmpid).or_default().push(r);
+            }
+            for group in by_cmp.values() {
+                let (delta, big_endian) = match classify_records(group) {
+                    FieldRole::Length { delta, big_endian } => (delta, big_endian),
+                    _ => continue,
+                };
+                let newest = group.iter().max_by_key(|r| r.id).and_then(|r| r.offsets.first());
+                if let Some(seg) = newest {
+                    let begin = seg.begin as usize;
+                    if !fields.iter().any(|f| f.begin == begin) {
+                        fields.push(LengthField {
+                            begin,
+                            width,
+                            delta,
+                            big_endian,
+                        });
+                    }
+                }
+            }
+        });
+    }
+    fields
+}
+
+// Rewrite the length fields that fit in `buf` for its current size.
+// Returns the offsets changed.
+pub fn fix_length_fields(buf: &mut [u8], fields: &[LengthField]) -> Vec<u32> {
+    let len = buf.len();
+    let mut changed = vec![];
+    for f in fields {
+        let end = f.begin + f.width;
+        if end > len {
+            continue;
+        }
+        if let Some(value) = encode_length(len, f.delta, f.big_endian, f.width) {
+            if buf[f.begin..end] != value[..] {
+                buf[f.begin..end].copy_from_slice(&value);
+                changed.extend(f.begin as u32..end as u32);
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(value: &[u8], input_len: u32, num_seen: usize) -> CondRecord {
+        CondRecord {
+            critical_values: vec![value.to_vec()],
+            input_len,
+            num_seen,
+            ..Default::default()
+        }
+    }
+
+    fn classify(records: &[CondRecord]) -> FieldRole {
+        classify_records(&records.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_classify_records() {
+        assert_eq!(classify(&[record(b"PK", 100, 3)]), FieldRole::Unknown);
+        assert_eq!(classify(&[record(b"PK", 100, 20)]), FieldRole::Magic);
+
+        // big endian length of the rest of the input after a 4 byte header
+        let lengths: Vec<CondRecord> = (0..20).map(|i| record(&[0, 60 + i], 64 + i as u32, 1)).collect();
+        assert_eq!(
+            classify(&lengths),
+            FieldRole::Length {
+                delta: -4,
+                big_endian: true
+            }
+        );
+
+        let enums: Vec<CondRecord> = (0..4).map(|i| record(&[i, 0], 100, 5)).collect();
+        assert_eq!(classify(&enums), FieldRole::Enum);
+
+        let checksums: Vec<CondRecord> = (0..20u8).map(|i| record(&[i.wrapping_mul(37), i ^ 0x5a], 100, 1)).collect();
+        assert_eq!(classify(&checksums), FieldRole::Checksum);
+
+        let mut mixed = checksums;
+        mixed[0].num_seen = 20;
+        assert_eq!(classify(&mixed), FieldRole::Unknown);
+    }
+
+    #[test]
+    fn test_cmp_role() {
+        // [4] with the values of many comparisons, most of them seen once
+        let mut records: Vec<CondRecord> = (0..40u32)
+            .map(|i| CondRecord {
+                cmpid: i,
+                ..record(&i.wrapping_mul(2654435761).to_le_bytes(), 100, 1)
+            })
+            .collect();
+        assert_eq!(classify(&records), FieldRole::Checksum);
+        // too few samples of the cond itself
+        assert_eq!(cmp_role(&records, 3), FieldRole::Unknown);
+        assert_eq!(cmp_role(&records, 100), FieldRole::Unknown);
+
+        records.push(CondRecord {
+            cmpid: 100,
+            ..record(b"\x7fELF", 100, 20)
+        });
+        assert_eq!(cmp_role(&records, 100), FieldRole::Magic);
+    }
+
+    #[test]
+    fn test_fix_length_fields() {
+        assert_eq!(encode_length(10, 2, false, 2), Some(vec![12, 0]));
+        assert_eq!(encode_length(10, 2, true, 4), Some(vec![0, 0, 0, 12]));
+        assert_eq!(encode_length(300, 0, false, 1), None);
+        assert_eq!(encode_length(1, -2, false, 1), None);
+
+        let fields = vec![
+            LengthField {
+                begin: 0,
+                width: 2,
+                delta: -2,
+                big_endian: true,
+            },
+            LengthField {
+                begin: 6,
+                width: 4,
+                delta: 0,
+                big_endian: false,
+            },
+        ];
+        let mut buf = vec![0, 4, 1, 2, 3, 4];
+        assert!(fix_length_fields(&mut buf, &fields).is_empty());
+        buf.extend_from_slice(&[5, 6, 7, 8]);
+        assert_eq!(fix_length_fields(&mut buf, &fields), vec![0, 1, 6, 7, 8, 9]);
+        assert_eq!(buf, vec![0, 8, 1, 2, 3, 4, 10, 0, 0, 0]);
+    }
+}
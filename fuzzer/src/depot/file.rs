@@ -1,6 +1,6 @@
 use std::{
     fs,
-    io::prelude::*,
+    io::{self, prelude::*, BufWriter},
     path::{Path, PathBuf},
 };
 
@@ -37,3 +37,18 @@ pub fn read_from_file(path: &Path) -> Vec<u8> {
 
     buf
 }
+
+// Write `path` through a tmp file renamed over it, so a reader, or a crash while writing,
+// never leaves it half written.
+pub fn write_atomically<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<fs::File>) -> io::Result<()>,
+{
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp_path, path)
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+#[derive(Default)]
+struct CacheInner {
+    // id -> (buf, use tick)
+    bufs: HashMap<usize, (Vec<u8>, u64)>,
+    // use tick -> id, oldest first
+    lru: BTreeMap<u64, usize>,
+    num_bytes: usize,
+    tick: u64,
+}
+
+impl CacheInner {
+    fn touch(&mut self, id: usize) -> Option<&Vec<u8>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (buf, last) = self.bufs.get_mut(&id)?;
+        self.lru.remove(last);
+        *last = tick;
+        self.lru.insert(tick, id);
+        Some(buf)
+    }
+}
+
+// Input buffers shared by the fuzzing threads, bounded by their total size.
+// Inputs never change once saved, so the cache is never invalidated.
+pub struct InputCache {
+    max_bytes: usize,
+    inner: Mutex<CacheInner>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl InputCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            inner: Mutex::new(Default::default()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    // The buffer of `id`, read by `load` on a miss.
+    // The lock is not held while loading, so two threads may load the same input once.
+    pub fn get<F>(&self, id: usize, load: F) -> Vec<u8>
+    where
+        F: FnOnce() -> Vec<u8>,
+    {
+        if let Some(buf) = self.inner.lock().unwrap().touch(id) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return buf.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let buf = load();
+        self.insert(id, &buf, false);
+        buf
+    }
+
+    // Write-through of a new input. Its id is taken before its file is written, so a
+    // concurrent `get` may have cached a partial read, which this replaces.
+    pub fn put(&self, id: usize, buf: &[u8]) {
+        self.insert(id, buf, true);
+    }
+
+    fn insert(&self, id: usize, buf: &[u8], replace: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if replace {
+            if let Some((old, tick)) = inner.bufs.remove(&id) {
+                inner.lru.remove(&tick);
+                inner.num_bytes -= old.len();
+            }
+        } else if inner.touch(id).is_some() {
+            return;
+        }
+        if buf.len() > self.max_bytes {
+            return;
+        }
+        while inner.num_bytes + buf.len() > self.max_bytes {
+            let oldest = match inner.lru.pop_first() {
+                Some((_, oldest)) => oldest,
+                None => break,
+            };
+            if let Some((evicted, _)) = inner.bufs.remove(&oldest) {
+                inner.num_bytes -= evicted.len();
+            }
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.lru.insert(tick, id);
+        inner.bufs.insert(id, (buf.to_vec(), tick));
+        inner.num_bytes += buf.len();
+    }
+
+    // (hits, misses)
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_cache() {
+        let cache = InputCache::new(8);
+        assert_eq!(cache.get(0, || vec![0; 4]), vec![0; 4]);
+        assert_eq!(cache.get(1, || vec![1; 4]), vec![1; 4]);
+        // cached, not loaded again
+        assert_eq!(cache.get(0, || unreachable!()), vec![0; 4]);
+        // 1 is the least recently used
+        assert_eq!(cache.get(2, || vec![2; 3]), vec![2; 3]);
+        assert_eq!(cache.get(0, || unreachable!()), vec![0; 4]);
+        assert_eq!(cache.get(1, || vec![1; 4]), vec![1; 4]);
+        // too large to be cached
+        assert_eq!(cache.get(3, || vec![3; 9]), vec![3; 9]);
+        assert_eq!(cache.get(3, || vec![4; 9]), vec![4; 9]);
+        assert_eq!(cache.stats(), (2, 6));
+
+        // a partial read is replaced by the saved input
+        assert_eq!(cache.get(4, || vec![4; 1]), vec![4; 1]);
+        cache.put(4, &[4; 2]);
+        assert_eq!(cache.get(4, || unreachable!()), vec![4; 2]);
+        // and is not cached over it
+        cache.put(5, &[5; 2]);
+        assert_eq!(cache.get(5, || unreachable!()), vec![5; 2]);
+    }
+}
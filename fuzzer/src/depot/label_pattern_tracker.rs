@@ -1,35 +1,180 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::Mutex;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    RwLock,
+};
 use lazy_static::lazy_static;
-use angora_common::tag::TagSeg;
+use angora_common::{defs, tag::TagSeg};
 use crate::cond_stmt::CondStmt;
 use std::fs::File;
-use std::io::{self, Write};
+use std::io::{self, BufReader, Write};
 use std::path::Path;
 use serde_derive::{Serialize, Deserialize};
-use super::depot::Depot;
+use super::file::write_atomically;
+use super::strategy::{RecordSource, Strategy};
+use super::pattern_store::{hash_values, next_use_tick, PatternLimits, PatternStore};
 
 pub type LabelPattern = Vec<u32>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// A solved cond is worth more than a new path when ranking records.
+const SOLVED_REWARD: f64 = 4.0;
+const CRASH_REWARD: f64 = 4.0;
+// Exploration weight of UCB1.
+const UCB_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+// What reusing a record has achieved so far.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RecordOutcome {
+    pub num_tried: usize,
+    pub num_new_paths: usize,
+    pub num_solved: usize,
+    pub num_crashes: usize,
+}
+
+impl RecordOutcome {
+    pub fn is_hit(&self) -> bool {
+        self.num_new_paths > 0 || self.num_solved > 0 || self.num_crashes > 0
+    }
+
+    pub fn merge(&mut self, other: &RecordOutcome) {
+        self.num_tried += other.num_tried;
+        self.num_new_paths += other.num_new_paths;
+        self.num_solved += other.num_solved;
+        self.num_crashes += other.num_crashes;
+    }
+
+    pub fn mean_reward(&self) -> f64 {
+        if self.num_tried == 0 {
+            return 0.0;
+        }
+        (self.num_new_paths as f64
+            + self.num_solved as f64 * SOLVED_REWARD
+            + self.num_crashes as f64 * CRASH_REWARD)
+            / self.num_tried as f64
+    }
+
+    // UCB1 score. Untried records always come first.
+    fn ucb_score(&self, total_tried: usize) -> f64 {
+        if self.num_tried == 0 {
+            return f64::INFINITY;
+        }
+        let total = (total_tried.max(1) as f64).ln();
+        self.mean_reward() + UCB_EXPLORATION * (total / self.num_tried as f64).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CondRecord {
+    #[serde(default)]
+    pub id: u64,
     pub cmpid: u32,
-    // pub order: u32,
-    // pub context: u32,
-    // pub op: u32,
-    // pub lb1: u32,
-    // pub lb2: u32,
-    // pub condition: u32,
-    // pub belong: u32,
-    // pub arg1: u64,
-    // pub arg2: u64,
+    // comparison that produced the values, missing in old dbs
+    #[serde(default)]
+    pub order: u32,
+    #[serde(default)]
+    pub context: u32,
+    #[serde(default)]
+    pub op: u32,
+    #[serde(default)]
+    pub lb1: u32,
+    #[serde(default)]
+    pub lb2: u32,
+    #[serde(default)]
+    pub condition: u32,
+    #[serde(default)]
+    pub belong: u32,
+    #[serde(default)]
+    pub arg1: u64,
+    #[serde(default)]
+    pub arg2: u64,
     pub offsets: Vec<TagSeg>,
     pub critical_values: Vec<Vec<u8>>,
+    #[serde(default)]
+    pub outcome: RecordOutcome,
+    // strategy that produced the input, and whether the values flipped the cond
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub solving: bool,
+    // the values come from a crashing input, reused first
+    #[serde(default)]
+    pub crash: bool,
+    // shifts from `offsets` at which relocated values hit
+    #[serde(default)]
+    pub hit_shifts: Vec<i32>,
+    // length of the input the values were taken from, and how many times they were recorded
+    #[serde(default)]
+    pub input_len: u32,
+    #[serde(default)]
+    pub num_seen: usize,
+    // use tick for LRU eviction
+    #[serde(skip)]
+    pub last_used: u64,
+}
+
+// On-disk form of LABEL_PATTERN_MAP. JSON maps need string keys,
+// so the patterns are stored as a list of (pattern, records) pairs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PatternDb {
+    pub patterns: Vec<(LabelPattern, Vec<CondRecord>)>,
+}
+
+// Records imported from the dbs of other targets.
+// They are read-only and kept apart from the local map, each tagged with the db it came from.
+#[derive(Debug, Default)]
+pub struct ImportedPatterns {
+    pub sources: Vec<String>,
+    pub map: HashMap<LabelPattern, Vec<(usize, CondRecord)>>,
+}
+
+static NEXT_RECORD_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_record_id() -> u64 {
+  NEXT_RECORD_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 lazy_static! {
-    pub static ref LABEL_PATTERN_MAP: Mutex<HashMap<LabelPattern, Vec<CondRecord>>> =
-      Mutex::new(HashMap::new());
+    pub static ref LABEL_PATTERN_MAP: PatternStore = Default::default();
+    pub static ref IMPORTED_PATTERN_MAP: RwLock<ImportedPatterns> =
+      RwLock::new(Default::default());
+}
+
+pub fn set_pattern_limits(limits: PatternLimits) {
+  LABEL_PATTERN_MAP.set_limits(limits);
+}
+
+fn segment_boundaries(pattern: &[u32]) -> HashSet<u32> {
+  pattern
+    .iter()
+    .scan(0, |end, &w| {
+      *end += w;
+      Some(*end)
+    })
+    .collect()
+}
+
+// Whether the values of `source` can be re-partitioned into the `target` layout:
+// the same total width, and one layout refines the other (e.g. [4], [2, 2] and [1, 1, 2]),
+// so that no segment straddles a boundary of the other.
+pub fn is_repartition_of(source: &[u32], target: &[u32]) -> bool {
+  if source == target || source.iter().sum::<u32>() != target.iter().sum::<u32>() {
+    return false;
+  }
+  let source_bounds = segment_boundaries(source);
+  let target_bounds = segment_boundaries(target);
+  source_bounds.is_subset(&target_bounds) || target_bounds.is_subset(&source_bounds)
+}
+
+// All patterns in the map whose records can be re-partitioned into `target`.
+pub fn find_repartition_patterns(target: &LabelPattern) -> Vec<LabelPattern> {
+  let mut patterns: Vec<LabelPattern> = LABEL_PATTERN_MAP
+    .patterns_of_width(target.iter().sum())
+    .into_iter()
+    .filter(|p| is_repartition_of(p, target))
+    .collect();
+  // Coarser layouts first: a whole field is more likely to stay meaningful.
+  patterns.sort_by_key(|p| (p.len(), p.clone()));
+  patterns
 }
 
 pub fn extract_pattern(offsets: &Vec<TagSeg>) -> LabelPattern {
@@ -66,7 +211,7 @@ pub fn extract_pattern_merged(offsets: &Vec<TagSeg>) -> LabelPattern {
   merged.iter().map(|seg| seg.end - seg.begin).collect()
 }
 
-fn extract_value_from_label(offsets: &Vec<TagSeg>, input_buf: &Vec<u8>) -> Vec<Vec<u8>> {
+fn extract_value_from_label(offsets: &Vec<TagSeg>, input_buf: &[u8]) -> Vec<Vec<u8>> {
   let merged_offsets = merge_continuous_segments(offsets);
   let mut critical_values = Vec::new();
 
@@ -91,7 +236,8 @@ fn extract_value_from_label(offsets: &Vec<TagSeg>, input_buf: &Vec<u8>) -> Vec<V
 fn create_record_for_offsets(
   offsets: &Vec<TagSeg>,
   cond: &CondStmt,
-  depot: &Depot,
+  source: &RecordSource,
+  solving: bool,
   operand_num: u8,
 ) {
   if offsets.is_empty() {
@@ -101,17 +247,18 @@ fn create_record_for_offsets(
   // 병합된 세그먼트 추출
   let merged_offsets = merge_continuous_segments(offsets);
   let pattern = extract_pattern_merged(offsets);
-  let input_buf = depot.get_input_buf(cond.base.belong as usize);
-  let critical_values = extract_value_from_label(offsets, &input_buf);
+  let critical_values = extract_value_from_label(offsets, source.buf);
 
   // 1. 전체 패턴 레코드 생성 (기존 로직)
-  create_single_record(
+  let mut batch = vec![create_single_record(
       &pattern,
       offsets,
       &critical_values,
       cond,
+      source,
+      solving,
       operand_num,
-  );
+  )];
 
   // 2. 패턴이 2개 이상의 세그먼트로 구성되어 있다면 개별 세그먼트도 추가
   if merged_offsets.len() > 1 {
@@ -120,83 +267,126 @@ fn create_record_for_offsets(
           let single_pattern = vec![merged_offsets[i].end - merged_offsets[i].begin];
           let single_critical_values = vec![critical_values[i].clone()];
 
-          create_single_record(
+          batch.push(create_single_record(
               &single_pattern,
               &single_segment,
               &single_critical_values,
               cond,
+              source,
+              solving,
               operand_num,
-          );
+          ));
       }
   }
+
+  // 중복 체크는 store가 shard lock 안에서 수행
+  LABEL_PATTERN_MAP.insert_batch(batch);
 }
 
 // 헬퍼 함수: 실제 레코드 생성 로직
 fn create_single_record(
   pattern: &LabelPattern,
   offsets: &Vec<TagSeg>,
-  critical_values: &Vec<Vec<u8>>,
+  critical_values: &[Vec<u8>],
   cond: &CondStmt,
+  source: &RecordSource,
+  solving: bool,
   operand_num: u8,
-) {
-  let mut map = LABEL_PATTERN_MAP.lock().unwrap();
-
-  // 중복 체크
-  if let Some(existing_records) = map.get(pattern) {
-      for existing in existing_records.iter() {
-          if existing.critical_values == *critical_values {
-              return;
-          }
-      }
-  }
-
+) -> (LabelPattern, CondRecord) {
   let record = CondRecord {
+      id: next_record_id(),
       cmpid: cond.base.cmpid,
-      // order: cond.base.order,
-      // context: cond.base.context,
-      // op: cond.base.op,
-      // lb1: cond.base.lb1,
-      // lb2: cond.base.lb2,
-      // condition: cond.base.condition,
-      // belong: cond.base.belong,
-      // arg1: cond.base.arg1,
-      // arg2: cond.base.arg2,
+      order: cond.base.order,
+      context: cond.base.context,
+      op: cond.base.op,
+      lb1: cond.base.lb1,
+      lb2: cond.base.lb2,
+      condition: cond.base.condition,
+      belong: cond.base.belong,
+      arg1: cond.base.arg1,
+      arg2: cond.base.arg2,
       offsets: offsets.clone(),
-      critical_values: critical_values.clone(),
+      critical_values: critical_values.to_vec(),
+      outcome: Default::default(),
+      strategy: source.strategy,
+      solving,
+      crash: source.crash,
+      hit_shifts: vec![],
+      input_len: source.buf.len() as u32,
+      num_seen: 1,
+      last_used: 0,
   };
 
-  map.entry(pattern.clone()).or_insert_with(Vec::new).push(record);
+  (pattern.clone(), record)
 }
 
-fn add_single_label_record(cond: &CondStmt, depot: &Depot) {
-    create_record_for_offsets(&cond.offsets, cond, depot, 0);
+fn add_single_label_record(cond: &CondStmt, source: &RecordSource, solving: bool) {
+    create_record_for_offsets(&cond.offsets, cond, source, solving, 0);
 }
 
-fn add_dual_label_records(cond: &CondStmt, depot: &Depot) {
+fn add_dual_label_records(cond: &CondStmt, source: &RecordSource, solving: bool) {
     if cond.offsets_opt.is_empty() {
         return;
     }
 
-    create_record_for_offsets(&cond.offsets, cond, depot, 1);
-    create_record_for_offsets(&cond.offsets_opt, cond, depot, 2);
+    create_record_for_offsets(&cond.offsets, cond, source, solving, 1);
+    create_record_for_offsets(&cond.offsets_opt, cond, source, solving, 2);
+}
+
+// The constant operand of a memcmp/strcmp cond: `variables` holds it in its first `size` bytes.
+fn magic_bytes(cond: &CondStmt) -> Option<&[u8]> {
+  let size = cond.base.size as usize;
+  if cond.base.op != defs::COND_FN_OP || size == 0 || cond.variables.len() < size {
+    return None;
+  }
+  Some(&cond.variables[..size])
 }
 
-pub fn add_cond_to_pattern_map(cond: &CondStmt, depot: &Depot) {
+// Store the magic bytes as a solving record keyed by their length,
+// so other conds of the same width can reuse them.
+fn add_magic_record(cond: &CondStmt, source: &RecordSource) {
+  if let Some(magic) = magic_bytes(cond) {
+    let pattern = vec![magic.len() as u32];
+    LABEL_PATTERN_MAP.insert_batch(vec![create_single_record(
+      &pattern,
+      &cond.offsets,
+      &[magic.to_vec()],
+      cond,
+      source,
+      true,
+      0,
+    )]);
+  }
+}
+
+// `solving`: the input of `source` flipped the cond.
+pub fn add_cond_to_pattern_map(cond: &CondStmt, source: &RecordSource, solving: bool) {
+  add_magic_record(cond, source);
+  add_taint_records(cond, source, solving);
+}
+
+// Record the values of the conds reached by a crashing input, without queueing the conds.
+pub fn add_crash_conds(conds: &[CondStmt], source: &RecordSource) {
+  for cond in conds {
+    add_taint_records(cond, source, false);
+  }
+}
+
+fn add_taint_records(cond: &CondStmt, source: &RecordSource, solving: bool) {
   if cond.base.lb1 > 0 && cond.base.lb2 == 0 {
-      add_single_label_record(cond, depot);
+      add_single_label_record(cond, source, solving);
   }
   else if cond.base.lb1 == 0 && cond.base.lb2 > 0 {
-      add_single_label_record(cond, depot);
+      add_single_label_record(cond, source, solving);
   }
   else if cond.base.lb1 > 0 && cond.base.lb2 > 0 {
-      add_dual_label_records(cond, depot);
+      add_dual_label_records(cond, source, solving);
   }
 }
 
 pub fn get_stats() -> (usize, usize) {
-  let map = LABEL_PATTERN_MAP.lock().unwrap();
-  let num_patterns = map.len();
-  let num_records: usize = map.values().map(|v| v.len()).sum();
+  let num_patterns = LABEL_PATTERN_MAP.num_patterns();
+  let num_records = LABEL_PATTERN_MAP.num_records();
   (num_patterns, num_records)
 }
 
@@ -219,27 +409,30 @@ fn check_continuous(offsets: &Vec<TagSeg>) -> bool {
 }
 
 pub fn save_to_text(path: &Path) -> io::Result<()> {
-  let map = LABEL_PATTERN_MAP.lock().unwrap();
+  let mut sorted_patterns = LABEL_PATTERN_MAP.snapshot();
+  sorted_patterns.sort_by(|(a, _), (b, _)| a.cmp(b));
   let mut file = File::create(path)?;
 
   writeln!(file, "# Angora Label Pattern Map")?;
   writeln!(file, "# Generated at: {}", chrono::Local::now())?;
-  writeln!(file, "# Total patterns: {}", map.len())?;
-  writeln!(file, "# Total records: {}", map.values().map(|v| v.len()).sum::<usize>())?;
+  writeln!(file, "# Total patterns: {}", sorted_patterns.len())?;
+  writeln!(file, "# Total records: {}", sorted_patterns.iter().map(|(_, r)| r.len()).sum::<usize>())?;
   writeln!(file)?;
 
-  let mut sorted_patterns: Vec<_> = map.iter().collect();
-  sorted_patterns.sort_by_key(|(pattern, _)| pattern.clone());
-
   for (pattern, records) in sorted_patterns {
       writeln!(file, "Pattern: {:?} (size: {})", pattern, pattern.iter().sum::<u32>())?;
       writeln!(file, "  Records: {}", records.len())?;
 
       for (i, record) in records.iter().enumerate() {
-        // writeln!(file, "    [{}] cmpid={}, order={}, context={}, op={:#x}, lb1={}, lb2={}, condition={}, belong={}, arg1={}, arg2={}", i, record.cmpid, record.order, record.context, record.op, record.lb1, record.lb2, record.condition, record.belong, record.arg1, record.arg2)?;
-        writeln!(file, "        Cmpid: {:?}", record.cmpid)?;
+        writeln!(file, "    [{}] cmpid={}, order={}, context={}, op={:#x}, lb1={}, lb2={}, condition={}, belong={}, arg1={}, arg2={}", i, record.cmpid, record.order, record.context, record.op, record.lb1, record.lb2, record.condition, record.belong, record.arg1, record.arg2)?;
+        writeln!(file, "        Strategy: {:?}, Solving: {}, Crash: {}", record.strategy, record.solving, record.crash)?;
+        writeln!(file, "        Input len: {}, Seen: {}", record.input_len, record.num_seen)?;
         writeln!(file, "        Offsets: {:?}", record.offsets)?;
+        if !record.hit_shifts.is_empty() {
+          writeln!(file, "        Hit shifts: {:?}", record.hit_shifts)?;
+        }
         writeln!(file, "        Critical values: {:?}", record.critical_values)?;
+        writeln!(file, "        Outcome: {:?}", record.outcome)?;
       }
       writeln!(file)?;
   }
@@ -248,31 +441,228 @@ pub fn save_to_text(path: &Path) -> io::Result<()> {
   Ok(())
 }
 
-pub fn get_next_records(
+// Checkpoint the map in a machine-readable form.
+pub fn save_to_db(path: &Path) -> io::Result<()> {
+  let db = PatternDb {
+    patterns: LABEL_PATTERN_MAP.snapshot(),
+  };
+
+  write_atomically(path, |writer| Ok(serde_json::to_writer(writer, &db)?))?;
+
+  debug!("[LabelPattern] Checkpointed {} patterns to {:?}", db.patterns.len(), path);
+  Ok(())
+}
+
+// Merge a db saved by `save_to_db` into the map. Returns the number of new records.
+pub fn load_from_db(path: &Path) -> io::Result<usize> {
+  load_db(path, false)
+}
+
+// Load the checkpoint of the session being resumed. The ids are kept,
+// since the restored conds remember the records reused on them by id.
+pub fn resume_from_db(path: &Path) -> io::Result<usize> {
+  load_db(path, true)
+}
+
+fn load_db(path: &Path, keep_ids: bool) -> io::Result<usize> {
+  let reader = BufReader::new(File::open(path)?);
+  let db: PatternDb = serde_json::from_reader(reader)?;
+
+  let mut batch = vec![];
+  for (pattern, records) in db.patterns {
+    for mut record in records {
+      if keep_ids {
+        NEXT_RECORD_ID.fetch_max(record.id + 1, Ordering::Relaxed);
+      } else {
+        // ids are only unique within one run
+        record.id = next_record_id();
+      }
+      batch.push((pattern.clone(), record));
+    }
+  }
+  let num_loaded = LABEL_PATTERN_MAP.insert_batch(batch);
+
+  info!("[LabelPattern] Loaded {} records from {:?}", num_loaded, path);
+  Ok(num_loaded)
+}
+
+// Import a db collected from a different target. Returns the number of new records.
+pub fn import_db(path: &Path) -> io::Result<usize> {
+  let reader = BufReader::new(File::open(path)?);
+  let db: PatternDb = serde_json::from_reader(reader)?;
+
+  let mut imported = IMPORTED_PATTERN_MAP.write().unwrap();
+  let source = imported.sources.len();
+  imported.sources.push(path.to_string_lossy().into_owned());
+
+  let mut num_imported = 0;
+  for (pattern, records) in db.patterns {
+    let entry = imported.map.entry(pattern).or_default();
+    // hash of the values -> slots in the entry, compared on a collision
+    let mut slots: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, (_, r)) in entry.iter().enumerate() {
+      slots.entry(hash_values(&r.critical_values)).or_default().push(i);
+    }
+    for record in records {
+      let same = slots.entry(hash_values(&record.critical_values)).or_default();
+      if same.iter().all(|&i| entry[i].1.critical_values != record.critical_values) {
+        same.push(entry.len());
+        entry.push((source, record));
+        num_imported += 1;
+      }
+    }
+  }
+
+  info!("[LabelPattern] Imported {} records from {:?}", num_imported, path);
+  Ok(num_imported)
+}
+
+// Store dictionary tokens as records keyed by their length.
+// Tokens are compared like magic bytes, so they rank with the equality conds.
+pub fn add_token_records(tokens: &[Vec<u8>]) -> usize {
+  let batch = tokens
+    .iter()
+    .map(|token| {
+      let record = CondRecord {
+        id: next_record_id(),
+        op: defs::COND_FN_OP,
+        critical_values: vec![token.clone()],
+        strategy: Strategy::Dict,
+        num_seen: 1,
+        ..Default::default()
+      };
+      (vec![token.len() as u32], record)
+    })
+    .collect();
+  LABEL_PATTERN_MAP.insert_batch(batch)
+}
+
+pub fn get_imported_source(source: usize) -> String {
+  let imported = IMPORTED_PATTERN_MAP.read().unwrap();
+  imported.sources.get(source).cloned().unwrap_or_default()
+}
+
+pub fn get_next_imported_records(
   cond: &mut CondStmt,
   pattern: &LabelPattern,
   iterations: usize
-) -> Option<Vec<CondRecord>> {
-  let selected = {
-    let map = LABEL_PATTERN_MAP.lock().unwrap();
-    let records = map.get(pattern)?;
+) -> Option<Vec<(usize, CondRecord)>> {
+  let imported = IMPORTED_PATTERN_MAP.read().unwrap();
+  let records = imported.map.get(pattern)?;
 
-    let total = records.len();
-    let start = cond.reusing_record_index;
+  let total = records.len();
+  let start = cond.reusing_imported_index;
 
-    if start >= total {
-        return None;
-    }
+  if start >= total {
+      return None;
+  }
 
-    let end = (start + iterations).min(total);
-    cond.reusing_record_index = end;
+  let end = (start + iterations).min(total);
+  cond.reusing_imported_index = end;
 
-    records[start..end].to_vec()
-  };
+  Some(records[start..end].to_vec())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpKind {
+  Equality,
+  Switch,
+  Ordering,
+  Float,
+  Other,
+}
+
+pub fn cmp_kind(op: u32) -> CmpKind {
+  let basic = op & defs::COND_BASIC_MASK;
+  if op == defs::COND_FN_OP {
+    // memcmp/strcmp against magic bytes
+    CmpKind::Equality
+  } else if op > defs::COND_MAX_EXPLORE_OP {
+    CmpKind::Other
+  } else if basic == defs::COND_SW_OP {
+    CmpKind::Switch
+  } else if basic <= defs::COND_FCMP_TRUE {
+    CmpKind::Float
+  } else if basic == defs::COND_ICMP_EQ_OP || basic == defs::COND_ICMP_NE_OP {
+    CmpKind::Equality
+  } else if (defs::COND_ICMP_UGT_OP..=defs::COND_ICMP_SLE_OP).contains(&basic) {
+    CmpKind::Ordering
+  } else {
+    CmpKind::Other
+  }
+}
+
+// Pick the indexes of at most `n` records that have not been tried on the cond yet.
+// Records from the same kind of comparison as `op` come first, then by their UCB score.
+fn rank_records(records: &[CondRecord], tried: &HashSet<u64>, op: u32, n: usize) -> Vec<usize> {
+  let kind = cmp_kind(op);
+  let total_tried: usize = records.iter().map(|r| r.outcome.num_tried).sum();
+  let mut candidates: Vec<(usize, bool, bool, f64)> = records
+    .iter()
+    .enumerate()
+    .filter(|(_, r)| !tried.contains(&r.id))
+    .map(|(i, r)| (i, r.crash, cmp_kind(r.op) == kind, r.outcome.ucb_score(total_tried)))
+    .collect();
+
+  // Values of crashing inputs first, to find related bugs.
+  // Stable sort: untried records keep their insertion order.
+  candidates.sort_by(|a, b| {
+    b.1.cmp(&a.1)
+      .then(b.2.cmp(&a.2))
+      .then(b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal))
+  });
+  candidates.truncate(n);
+  candidates.into_iter().map(|(i, _, _, _)| i).collect()
+}
+
+// Select the next records to reuse on the cond, favouring the productive ones.
+pub fn get_next_records(
+  cond: &mut CondStmt,
+  pattern: &LabelPattern,
+  iterations: usize
+) -> Option<Vec<CondRecord>> {
+  let selected: Vec<CondRecord> = LABEL_PATTERN_MAP.with_records_mut(pattern, |records| {
+    rank_records(records, &cond.reused_records, cond.base.op, iterations)
+      .into_iter()
+      .map(|i| {
+        records.touch(i);
+        records[i].clone()
+      })
+      .collect()
+  })?;
+
+  if selected.is_empty() {
+    return None;
+  }
+
+  for record in &selected {
+    cond.reused_records.insert(record.id);
+  }
 
   Some(selected)
 }
 
+// Credit the result of reusing a record back to it.
+pub fn update_record_outcome(pattern: &LabelPattern, id: u64, outcome: &RecordOutcome) {
+  LABEL_PATTERN_MAP.with_records_mut(pattern, |records| {
+    if let Some(record) = records.get_mut(id) {
+      record.outcome.merge(outcome);
+      record.last_used = next_use_tick();
+    }
+  });
+}
+
+// Remember a shift that worked, so relocation tries it first next time.
+pub fn credit_record_shift(pattern: &LabelPattern, id: u64, shift: i32) {
+  LABEL_PATTERN_MAP.with_records_mut(pattern, |records| {
+    if let Some(record) = records.get_mut(id) {
+      if !record.hit_shifts.contains(&shift) {
+        record.hit_shifts.push(shift);
+      }
+    }
+  });
+}
+
 // Check if any taint offset overlaps with mutated offsets
 fn offsets_overlap(taint_offsets: &Vec<TagSeg>, mutated_offsets: &HashSet<u32>) -> bool {
   for seg in taint_offsets {
@@ -288,13 +678,17 @@ fn offsets_overlap(taint_offsets: &Vec<TagSeg>, mutated_offsets: &HashSet<u32>)
 // Add cond to pattern map only if its offsets overlap with mutated offsets
 pub fn add_cond_to_pattern_map_with_filter(
   cond: &CondStmt,
-  depot: &Depot,
+  source: &RecordSource,
+  solving: bool,
   mutated_offsets: &HashSet<u32>
 ) {
+  // Magic bytes come from the program, not from the mutated bytes
+  add_magic_record(cond, source);
+
   // If mutated_offsets is empty, add without filtering (for initial seeds or non-mutation cases)
   if mutated_offsets.is_empty() {
     debug!("[LabelPattern] mutated_offsets is empty, adding without filter");
-    add_cond_to_pattern_map(cond, depot);
+    add_taint_records(cond, source, solving);
     return;
   }
 
@@ -310,5 +704,78 @@ pub fn add_cond_to_pattern_map_with_filter(
 
   debug!("[LabelPattern] Overlap found - adding to pattern map");
   // If overlaps, add to pattern map
-  add_cond_to_pattern_map(cond, depot);
-}
\ No newline at end of file
+  add_taint_records(cond, source, solving);
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(id: u64, num_tried: usize, num_new_paths: usize, num_solved: usize) -> CondRecord {
+    CondRecord {
+      id,
+      op: defs::COND_ICMP_EQ_OP,
+      critical_values: vec![vec![id as u8]],
+      outcome: RecordOutcome {
+        num_tried,
+        num_new_paths,
+        num_solved,
+        num_crashes: 0,
+      },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_rank_records() {
+    let records = vec![
+      record(1, 10, 0, 0),
+      record(2, 10, 2, 1),
+      record(3, 0, 0, 0),
+      record(4, 10, 1, 0),
+      record(5, 0, 0, 0),
+    ];
+    let mut tried = HashSet::new();
+    let op = defs::COND_ICMP_EQ_OP;
+    // untried first, in insertion order, then by reward
+    assert_eq!(rank_records(&records, &tried, op, 5), vec![2, 4, 1, 3, 0]);
+    assert_eq!(rank_records(&records, &tried, op, 3), vec![2, 4, 1]);
+
+    tried.insert(3);
+    tried.insert(2);
+    assert_eq!(rank_records(&records, &tried, op, 5), vec![4, 3, 0]);
+
+    // crash values go first
+    let mut records = records;
+    records[0].crash = true;
+    assert_eq!(rank_records(&records, &tried, op, 2), vec![0, 4]);
+  }
+
+  #[test]
+  fn test_rank_records_by_cmp_kind() {
+    let mut records = vec![
+      record(1, 0, 0, 0),
+      record(2, 0, 0, 0),
+      record(3, 10, 2, 1),
+    ];
+    records[1].op = defs::COND_SW_OP;
+    records[2].op = defs::COND_ICMP_NE_OP | defs::COND_SIGN_MASK;
+    let tried = HashSet::new();
+    assert_eq!(rank_records(&records, &tried, defs::COND_ICMP_EQ_OP, 3), vec![0, 2, 1]);
+    assert_eq!(rank_records(&records, &tried, defs::COND_SW_OP, 3), vec![1, 0, 2]);
+    assert_eq!(cmp_kind(defs::COND_ICMP_SLT_OP), CmpKind::Ordering);
+    assert_eq!(cmp_kind(defs::COND_FCMP_OEQ), CmpKind::Float);
+    assert_eq!(cmp_kind(defs::COND_FN_OP), CmpKind::Equality);
+    assert_eq!(cmp_kind(defs::COND_AFL_OP), CmpKind::Other);
+  }
+
+  #[test]
+  fn test_is_repartition_of() {
+    assert!(is_repartition_of(&[4], &[2, 2]));
+    assert!(is_repartition_of(&[2, 2], &[4]));
+    assert!(is_repartition_of(&[1, 1, 1, 1], &[2, 2]));
+    assert!(is_repartition_of(&[2, 1, 1], &[2, 2]));
+    assert!(!is_repartition_of(&[1, 3], &[2, 2]));
+    assert!(!is_repartition_of(&[2, 2], &[2, 2]));
+    assert!(!is_repartition_of(&[4], &[2, 1]));
+  }
+}
@@ -1,19 +1,43 @@
 mod depot;
 mod depot_dir;
+mod dict;
 mod dump;
+mod field_role;
+mod input_cache;
 mod file;
 mod qpriority;
+mod scheduler;
 mod sync;
 mod label_pattern_tracker;
+mod pattern_store;
+mod strategy;
 
-pub use self::{depot::Depot, file::*, sync::*};
+pub use self::{depot::Depot, dict::{dict_tokens, export_dict, load_dicts, parse_dict}, file::*, sync::*};
 pub use self::label_pattern_tracker::{
   add_cond_to_pattern_map,
+  add_crash_conds,
   print_stats as print_pattern_stats,
   save_to_text,
+  save_to_db,
+  load_from_db,
+  resume_from_db,
   LABEL_PATTERN_MAP,
   extract_pattern_merged,
   CondRecord,
+  LabelPattern,
+  PatternDb,
+  RecordOutcome,
+  update_record_outcome,
+  credit_record_shift,
   get_next_records,
+  find_repartition_patterns,
+  get_next_imported_records,
+  get_imported_source,
+  import_db,
+  set_pattern_limits,
 };
-use self::{depot_dir::DepotDir, qpriority::QPriority};
+pub use self::field_role::{classify_records, encode_length, fix_length_fields, length_fields, pattern_role, FieldRole, LengthField};
+pub use self::scheduler::{SchedulePolicy, Scheduler};
+pub use self::strategy::{RecordSource, Strategy};
+pub use self::pattern_store::{hash_values, EvictionPolicy, PatternLimits, PatternRecords, PatternStore};
+use self::{depot_dir::DepotDir, input_cache::InputCache, qpriority::QPriority};
@@ -0,0 +1,495 @@
+use super::label_pattern_tracker::{CondRecord, LabelPattern};
+use angora_common::config;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        OnceLock, RwLock,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    Lru,
+    LeastProductive,
+}
+
+impl EvictionPolicy {
+    pub fn from(policy: &str) -> Self {
+        match policy {
+            "lru" => EvictionPolicy::Lru,
+            "productive" => EvictionPolicy::LeastProductive,
+            _ => unreachable!(),
+        }
+    }
+}
+
+// Caps of the pattern map, 0 means unlimited.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternLimits {
+    pub max_per_pattern: usize,
+    pub max_total: usize,
+    pub eviction: EvictionPolicy,
+}
+
+impl Default for PatternLimits {
+    fn default() -> Self {
+        Self {
+            max_per_pattern: config::MAX_RECORDS_PER_PATTERN,
+            max_total: config::MAX_PATTERN_RECORDS,
+            eviction: EvictionPolicy::LeastProductive,
+        }
+    }
+}
+
+static USE_TICK: AtomicU64 = AtomicU64::new(1);
+
+// Logical clock for LRU.
+pub fn next_use_tick() -> u64 {
+    USE_TICK.fetch_add(1, Ordering::Relaxed)
+}
+
+pub fn hash_values(critical_values: &[Vec<u8>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    critical_values.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Records of one pattern, deduplicated by their critical values and indexed by hash and id.
+#[derive(Debug, Default)]
+pub struct PatternRecords {
+    records: Vec<CondRecord>,
+    // hash of the critical values -> slots of the records, more than one on a collision
+    by_hash: HashMap<u64, Vec<usize>>,
+    // record id -> slot
+    by_id: HashMap<u64, usize>,
+}
+
+impl Deref for PatternRecords {
+    type Target = [CondRecord];
+    fn deref(&self) -> &Self::Target {
+        &self.records
+    }
+}
+
+impl<'a> IntoIterator for &'a PatternRecords {
+    type Item = &'a CondRecord;
+    type IntoIter = std::slice::Iter<'a, CondRecord>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.iter()
+    }
+}
+
+impl PatternRecords {
+    fn find(&self, hash: u64, critical_values: &[Vec<u8>]) -> Option<usize> {
+        self.by_hash
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|&i| self.records[i].critical_values == critical_values)
+    }
+
+    pub fn contains(&self, critical_values: &[Vec<u8>]) -> bool {
+        self.find(hash_values(critical_values), critical_values).is_some()
+    }
+
+    // Returns false if a record with the same values exists.
+    // A duplicate is counted in the existing record, and still marks it
+    // if it flipped its cond or came from a crash.
+    pub fn insert(&mut self, record: CondRecord) -> bool {
+        let hash = hash_values(&record.critical_values);
+        self.insert_hashed(hash, record)
+    }
+
+    fn insert_hashed(&mut self, hash: u64, mut record: CondRecord) -> bool {
+        if let Some(i) = self.find(hash, &record.critical_values) {
+            let existing = &mut self.records[i];
+            existing.num_seen += record.num_seen.max(1);
+            if record.solving && !existing.solving {
+                existing.solving = true;
+                existing.strategy = record.strategy;
+            }
+            existing.crash |= record.crash;
+            return false;
+        }
+        record.last_used = next_use_tick();
+        let slot = self.records.len();
+        self.by_hash.entry(hash).or_default().push(slot);
+        self.by_id.insert(record.id, slot);
+        self.records.push(record);
+        true
+    }
+
+    pub fn touch(&mut self, index: usize) {
+        self.records[index].last_used = next_use_tick();
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut CondRecord> {
+        let slot = *self.by_id.get(&id)?;
+        Some(&mut self.records[slot])
+    }
+
+    // The slots after a removed record move down by one.
+    fn reindex(&mut self) {
+        self.by_hash.clear();
+        self.by_id.clear();
+        for (slot, record) in self.records.iter().enumerate() {
+            self.by_hash.entry(hash_values(&record.critical_values)).or_default().push(slot);
+            self.by_id.insert(record.id, slot);
+        }
+    }
+
+    // Remove the least useful record under the policy.
+    // LeastProductive drops the record with the lowest mean reward first. Untried records
+    // get the mean reward of the pattern as a prior, so they outlive the unproductive
+    // tried records but not the productive ones. Ties go to tried records, then the oldest.
+    pub fn evict(&mut self, policy: EvictionPolicy) -> Option<CondRecord> {
+        let victim = match policy {
+            EvictionPolicy::Lru => self
+                .records
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, r)| r.last_used)
+                .map(|(i, _)| i),
+            EvictionPolicy::LeastProductive => {
+                let (reward, tried) = self.records.iter().fold((0.0, 0), |(reward, tried), r| {
+                    (reward + r.outcome.mean_reward() * r.outcome.num_tried as f64, tried + r.outcome.num_tried)
+                });
+                // one more try without reward, so the prior stays below the best records
+                let prior = reward / (tried + 1) as f64;
+                let key = |r: &CondRecord| {
+                    let untried = r.outcome.num_tried == 0;
+                    let score = if untried { prior } else { r.outcome.mean_reward() };
+                    (score, untried, r.last_used)
+                };
+                self.records
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(i, _)| i)
+            },
+        }?;
+
+        let record = self.records.remove(victim);
+        self.reindex();
+        Some(record)
+    }
+}
+
+const NUM_SHARDS: usize = 64;
+
+type Shard = HashMap<LabelPattern, PatternRecords>;
+
+// Label pattern -> records, kept under the caps of `limits`.
+// Patterns are spread over shards with their own locks, so that the jobs
+// only contend when they touch patterns of the same shard.
+#[derive(Debug)]
+pub struct PatternStore {
+    shards: Vec<RwLock<Shard>>,
+    // total width -> patterns with that width
+    widths: RwLock<HashMap<u32, HashSet<LabelPattern>>>,
+    // size of the largest pattern of each shard, to find where to evict without locking them all
+    largest: Vec<AtomicUsize>,
+    num_records: AtomicUsize,
+    // set once before fuzzing, so reading it takes no lock
+    limits: OnceLock<PatternLimits>,
+}
+
+impl Default for PatternStore {
+    fn default() -> Self {
+        Self::with_shards(NUM_SHARDS)
+    }
+}
+
+impl PatternStore {
+    pub fn with_shards(num_shards: usize) -> Self {
+        Self {
+            shards: (0..num_shards.max(1)).map(|_| RwLock::new(HashMap::new())).collect(),
+            widths: RwLock::new(HashMap::new()),
+            largest: (0..num_shards.max(1)).map(|_| AtomicUsize::new(0)).collect(),
+            num_records: AtomicUsize::new(0),
+            limits: OnceLock::new(),
+        }
+    }
+
+    pub fn set_limits(&self, limits: PatternLimits) {
+        if self.limits.set(limits).is_err() {
+            warn!("The limits of the pattern store can only be set once");
+        }
+    }
+
+    fn limits(&self) -> PatternLimits {
+        self.limits.get().copied().unwrap_or_default()
+    }
+
+    fn shard_index(&self, pattern: &LabelPattern) -> usize {
+        let mut hasher = DefaultHasher::new();
+        pattern.hash(&mut hasher);
+        hasher.finish() as usize % self.shards.len()
+    }
+
+    fn shard(&self, pattern: &LabelPattern) -> &RwLock<Shard> {
+        &self.shards[self.shard_index(pattern)]
+    }
+
+    pub fn num_patterns(&self) -> usize {
+        self.shards.iter().map(|s| s.read().unwrap().len()).sum()
+    }
+
+    pub fn num_records(&self) -> usize {
+        self.num_records.load(Ordering::Relaxed)
+    }
+
+    // Read the records of a pattern under the shard's read lock.
+    pub fn with_records<R, F>(&self, pattern: &LabelPattern, f: F) -> Option<R>
+    where
+        F: FnOnce(&PatternRecords) -> R,
+    {
+        self.shard(pattern).read().unwrap().get(pattern).map(f)
+    }
+
+    pub fn with_records_mut<R, F>(&self, pattern: &LabelPattern, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut PatternRecords) -> R,
+    {
+        self.shard(pattern).write().unwrap().get_mut(pattern).map(f)
+    }
+
+    pub fn patterns_of_width(&self, width: u32) -> Vec<LabelPattern> {
+        match self.widths.read().unwrap().get(&width) {
+            Some(patterns) => patterns.iter().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    // Copy of the whole store, shard by shard.
+    pub fn snapshot(&self) -> Vec<(LabelPattern, Vec<CondRecord>)> {
+        let mut patterns = vec![];
+        for shard in &self.shards {
+            let shard = shard.read().unwrap();
+            patterns.extend(shard.iter().map(|(p, r)| (p.clone(), r.to_vec())));
+        }
+        patterns
+    }
+
+    // Returns false if the record is a duplicate.
+    pub fn insert(&self, pattern: &LabelPattern, record: CondRecord) -> bool {
+        self.insert_batch(vec![(pattern.clone(), record)]) > 0
+    }
+
+    fn update_largest(&self, i: usize, shard: &Shard) {
+        let largest = shard.values().map(|r| r.len()).max().unwrap_or(0);
+        self.largest[i].store(largest, Ordering::Relaxed);
+    }
+
+    // Insert records taking each shard lock once. Returns the number of new records.
+    // Evicts from a pattern itself when it is full, and from the largest pattern of
+    // the store when the whole store is full.
+    pub fn insert_batch(&self, batch: Vec<(LabelPattern, CondRecord)>) -> usize {
+        let limits = self.limits();
+        let mut batch: Vec<(usize, LabelPattern, CondRecord)> = batch
+            .into_iter()
+            .map(|(pattern, record)| (self.shard_index(&pattern), pattern, record))
+            .collect();
+        batch.sort_by_key(|(i, _, _)| *i);
+
+        let mut num_inserted = 0;
+        let mut new_patterns = vec![];
+        let mut items = batch.into_iter().peekable();
+        while let Some(&(i, _, _)) = items.peek() {
+            let mut shard = self.shards[i].write().unwrap();
+            while let Some((_, pattern, record)) = items.next_if(|(j, _, _)| *j == i) {
+                if !shard.contains_key(&pattern) {
+                    new_patterns.push(pattern.clone());
+                }
+                let records = shard.entry(pattern).or_default();
+                if !records.insert(record) {
+                    continue;
+                }
+                num_inserted += 1;
+                self.num_records.fetch_add(1, Ordering::Relaxed);
+
+                if limits.max_per_pattern > 0 {
+                    while records.len() > limits.max_per_pattern && records.evict(limits.eviction).is_some() {
+                        self.num_records.fetch_sub(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            self.update_largest(i, &shard);
+        }
+
+        if !new_patterns.is_empty() {
+            let mut widths = self.widths.write().unwrap();
+            for pattern in new_patterns {
+                widths.entry(pattern.iter().sum()).or_default().insert(pattern);
+            }
+        }
+
+        if limits.max_total > 0 {
+            self.evict_over_total(limits);
+        }
+        num_inserted
+    }
+
+    // Evict from the largest pattern of the store until it is under its cap.
+    // One shard is locked at a time, picked by its size hint.
+    fn evict_over_total(&self, limits: PatternLimits) {
+        let mut emptied = vec![];
+        while self.num_records() > limits.max_total {
+            let (i, largest) = self
+                .largest
+                .iter()
+                .map(|l| l.load(Ordering::Relaxed))
+                .enumerate()
+                .max_by_key(|(_, l)| *l)
+                .unwrap();
+            if largest == 0 {
+                break;
+            }
+            let mut shard = self.shards[i].write().unwrap();
+            let victim = shard.iter_mut().max_by_key(|(_, r)| r.len());
+            if let Some((pattern, records)) = victim {
+                if records.evict(limits.eviction).is_some() {
+                    self.num_records.fetch_sub(1, Ordering::Relaxed);
+                }
+                if records.is_empty() {
+                    let pattern = pattern.clone();
+                    shard.remove(&pattern);
+                    emptied.push(pattern);
+                }
+            }
+            self.update_largest(i, &shard);
+        }
+
+        if !emptied.is_empty() {
+            let mut widths = self.widths.write().unwrap();
+            for pattern in emptied {
+                let width = pattern.iter().sum();
+                if let Some(patterns) = widths.get_mut(&width) {
+                    patterns.remove(&pattern);
+                    if patterns.is_empty() {
+                        widths.remove(&width);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::depot::Strategy;
+
+    fn record(id: u64, value: u8, num_tried: usize, num_new_paths: usize) -> CondRecord {
+        let mut record = CondRecord {
+            id,
+            critical_values: vec![vec![value]],
+            ..Default::default()
+        };
+        record.outcome.num_tried = num_tried;
+        record.outcome.num_new_paths = num_new_paths;
+        record
+    }
+
+    fn ids(records: &PatternRecords) -> Vec<u64> {
+        records.iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut records = PatternRecords::default();
+        assert!(records.insert(record(1, 1, 4, 0)));
+        assert!(records.insert(record(2, 2, 0, 0)));
+        assert!(records.insert(record(3, 3, 4, 2)));
+        assert!(!records.insert(record(4, 1, 0, 0)));
+        // the duplicate is counted in the stored record
+        assert_eq!(records[0].num_seen, 1);
+
+        let mut lru = PatternRecords::default();
+        for r in records.iter() {
+            lru.insert(r.clone());
+        }
+
+        // the tried record without any reward goes first, then the untried one,
+        // which is not expected to do better than the pattern's productive record
+        assert_eq!(records.evict(EvictionPolicy::LeastProductive).unwrap().id, 1);
+        assert_eq!(records.evict(EvictionPolicy::LeastProductive).unwrap().id, 2);
+        assert_eq!(ids(&records), vec![3]);
+        // the value can be inserted again once evicted
+        assert!(records.contains(&[vec![3]]));
+        assert!(!records.contains(&[vec![1]]));
+
+        // with no reward in the pattern, an untried record outlives a tried one
+        let mut unproductive = PatternRecords::default();
+        unproductive.insert(record(6, 6, 0, 0));
+        unproductive.insert(record(7, 7, 3, 0));
+        assert_eq!(unproductive.evict(EvictionPolicy::LeastProductive).unwrap().id, 7);
+
+        assert_eq!(lru.evict(EvictionPolicy::Lru).unwrap().id, 1);
+        assert_eq!(ids(&lru), vec![2, 3]);
+
+        // a solving duplicate upgrades the stored record
+        let mut solving = record(5, 2, 0, 0);
+        solving.solving = true;
+        solving.strategy = Strategy::Gd;
+        assert!(!lru.insert(solving));
+        assert!(lru[0].solving);
+        assert_eq!(lru[0].strategy, Strategy::Gd);
+    }
+
+    #[test]
+    fn test_hash_collision() {
+        let mut records = PatternRecords::default();
+        assert!(records.insert_hashed(7, record(1, 1, 0, 0)));
+        // other values with the same hash are kept
+        assert!(records.insert_hashed(7, record(2, 2, 0, 0)));
+        assert!(!records.insert_hashed(7, record(3, 2, 0, 0)));
+        assert_eq!(records[1].num_seen, 1);
+        assert_eq!(records.get_mut(2).unwrap().critical_values, vec![vec![2]]);
+
+        records.evict(EvictionPolicy::Lru);
+        assert_eq!(ids(&records), vec![2]);
+        assert_eq!(records.get_mut(2).unwrap().critical_values, vec![vec![2]]);
+        assert!(records.get_mut(1).is_none());
+    }
+
+    #[test]
+    fn test_store_limits() {
+        let store = PatternStore::default();
+        store.set_limits(PatternLimits {
+            max_per_pattern: 3,
+            max_total: 4,
+            eviction: EvictionPolicy::Lru,
+        });
+
+        for i in 0..4 {
+            store.insert(&vec![1], record(i, i as u8, 0, 0));
+        }
+        assert_eq!(store.with_records(&vec![1], ids), Some(vec![1, 2, 3]));
+
+        store.insert(&vec![2], record(10, 10, 0, 0));
+        store.insert(&vec![2], record(11, 11, 0, 0));
+        // the whole map is full, the oldest record of the largest pattern goes,
+        // whichever shard it is in
+        assert_eq!(store.num_records(), 4);
+        assert_eq!(store.with_records(&vec![1], ids), Some(vec![2, 3]));
+        assert_eq!(store.with_records(&vec![2], ids), Some(vec![10, 11]));
+        assert_eq!(store.patterns_of_width(2), vec![vec![2]]);
+
+        // emptied patterns leave the store and the width index
+        let store = PatternStore::default();
+        store.set_limits(PatternLimits {
+            max_per_pattern: 0,
+            max_total: 1,
+            eviction: EvictionPolicy::Lru,
+        });
+        store.insert(&vec![5], record(1, 1, 0, 0));
+        store.insert(&vec![6], record(2, 2, 0, 0));
+        assert_eq!(store.num_records(), 1);
+        assert_eq!(store.num_patterns(), 1);
+        assert_eq!(store.patterns_of_width(5).len() + store.patterns_of_width(6).len(), 1);
+    }
+}
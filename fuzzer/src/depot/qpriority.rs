@@ -15,12 +15,21 @@ impl QPriority {
             self.base_inc()
         }
     }
+    // never reaches the done priority
     fn base_inc(&self) -> Self {
-        QPriority(self.0 + 1)
+        QPriority(self.0.saturating_add(1).min(DONE_PRIORITY - 1))
     }
 
     fn afl_inc(&self) -> Self {
-        QPriority(self.0 + 2)
+        QPriority(self.0.saturating_add(2).min(DONE_PRIORITY - 1))
+    }
+
+    pub fn new(value: usize) -> Self {
+        QPriority(value.min(DONE_PRIORITY as usize - 1) as u16)
+    }
+
+    pub fn value(&self) -> usize {
+        self.0 as usize
     }
 
     pub fn init(op: u32) -> Self {
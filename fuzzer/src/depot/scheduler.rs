@@ -0,0 +1,172 @@
+use super::QPriority;
+use crate::cond_stmt::CondStmt;
+use angora_common::defs;
+use std::{collections::HashMap, sync::Mutex};
+
+// Weights of the policies, in picks of the cond.
+const RARITY_WEIGHT: usize = 2;
+const GAIN_BONUS: usize = 4;
+const OPTIMA_COST: usize = 2;
+
+// Decides the order in which the depot hands out conds. Lower priorities run first.
+pub trait Scheduler: Send + Sync {
+    // Priority of a new cond.
+    fn init(&self, cond: &CondStmt) -> QPriority;
+
+    // Priority of a cond just picked, so that the other threads move on.
+    fn next(&self, cond: &CondStmt, priority: QPriority) -> QPriority {
+        priority.inc(cond.base.op)
+    }
+
+    // Priority of a cond after its round.
+    fn update(&self, _cond: &CondStmt, priority: QPriority) -> QPriority {
+        priority
+    }
+
+    // A cond reached by a tracked input, queued or not.
+    fn observe(&self, _cond: &CondStmt) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchedulePolicy {
+    #[default]
+    RoundRobin,
+    EdgeRarity,
+    CoverageGain,
+    SolveCost,
+}
+
+impl SchedulePolicy {
+    pub fn from(policy: &str) -> Self {
+        match policy {
+            "rr" => SchedulePolicy::RoundRobin,
+            "rarity" => SchedulePolicy::EdgeRarity,
+            "gain" => SchedulePolicy::CoverageGain,
+            "cost" => SchedulePolicy::SolveCost,
+            _ => {
+                warn!("Unknown schedule policy {}, use round-robin", policy);
+                SchedulePolicy::RoundRobin
+            },
+        }
+    }
+
+    pub fn scheduler(self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulePolicy::RoundRobin => Box::new(RoundRobin),
+            SchedulePolicy::EdgeRarity => Box::new(EdgeRarity::default()),
+            SchedulePolicy::CoverageGain => Box::new(CoverageGain),
+            SchedulePolicy::SolveCost => Box::new(SolveCost),
+        }
+    }
+}
+
+// AFL conds are picked half as often, as with `QPriority::inc`.
+fn picks(cond: &CondStmt) -> usize {
+    if cond.base.op == defs::COND_AFL_OP {
+        cond.fuzz_times * 2
+    } else {
+        cond.fuzz_times
+    }
+}
+
+// The least picked cond first.
+pub struct RoundRobin;
+
+impl Scheduler for RoundRobin {
+    fn init(&self, cond: &CondStmt) -> QPriority {
+        QPriority::init(cond.base.op)
+    }
+}
+
+// Conds whose cmp is reached by fewer inputs first, since their edges are rarely covered.
+#[derive(Default)]
+pub struct EdgeRarity {
+    hits: Mutex<HashMap<u32, usize>>,
+}
+
+impl EdgeRarity {
+    fn penalty(&self, cmpid: u32) -> usize {
+        let hits = self.hits.lock().unwrap().get(&cmpid).copied().unwrap_or(0);
+        RARITY_WEIGHT * (hits + 1).ilog2() as usize
+    }
+}
+
+impl Scheduler for EdgeRarity {
+    fn init(&self, cond: &CondStmt) -> QPriority {
+        QPriority::new(self.penalty(cond.base.cmpid))
+    }
+
+    fn update(&self, cond: &CondStmt, _priority: QPriority) -> QPriority {
+        QPriority::new(picks(cond) + self.penalty(cond.base.cmpid))
+    }
+
+    fn observe(&self, cond: &CondStmt) {
+        *self.hits.lock().unwrap().entry(cond.base.cmpid).or_default() += 1;
+    }
+}
+
+// Conds whose last round found new paths come back sooner.
+pub struct CoverageGain;
+
+impl Scheduler for CoverageGain {
+    fn init(&self, cond: &CondStmt) -> QPriority {
+        QPriority::init(cond.base.op)
+    }
+
+    fn update(&self, cond: &CondStmt, priority: QPriority) -> QPriority {
+        QPriority::new(priority.value().saturating_sub(GAIN_BONUS * cond.recent_gain))
+    }
+}
+
+// Conds that look cheap to solve first: fewer rounds so far, and fewer local minima met by the search.
+pub struct SolveCost;
+
+impl Scheduler for SolveCost {
+    fn init(&self, cond: &CondStmt) -> QPriority {
+        QPriority::init(cond.base.op)
+    }
+
+    fn update(&self, cond: &CondStmt, _priority: QPriority) -> QPriority {
+        QPriority::new(picks(cond) + OPTIMA_COST * cond.num_minimal_optima)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cond(cmpid: u32, fuzz_times: usize) -> CondStmt {
+        let mut cond = CondStmt::new();
+        cond.base.cmpid = cmpid;
+        cond.fuzz_times = fuzz_times;
+        cond
+    }
+
+    #[test]
+    fn test_schedulers() {
+        let rarity = EdgeRarity::default();
+        let (hot, rare) = (cond(1, 2), cond(2, 2));
+        for _ in 0..7 {
+            rarity.observe(&hot);
+        }
+        rarity.observe(&rare);
+        assert_eq!(rarity.init(&hot), QPriority::new(6));
+        assert!(rarity.update(&rare, QPriority::new(0)) > rarity.update(&hot, QPriority::new(0)));
+
+        let mut gaining = cond(3, 5);
+        let priority = QPriority::new(5);
+        assert_eq!(CoverageGain.update(&gaining, priority), priority);
+        gaining.recent_gain = 1;
+        assert_eq!(CoverageGain.update(&gaining, priority), QPriority::new(1));
+        gaining.recent_gain = 10;
+        assert_eq!(CoverageGain.update(&gaining, priority), QPriority::new(0));
+
+        let mut stuck = cond(4, 1);
+        stuck.num_minimal_optima = 3;
+        assert_eq!(SolveCost.update(&stuck, priority), QPriority::new(7));
+        let mut afl = cond(5, 1);
+        afl.base.op = defs::COND_AFL_OP;
+        assert_eq!(SolveCost.update(&afl, priority), QPriority::new(2));
+        assert!(QPriority::new(usize::MAX).inc(defs::COND_AFL_OP) != QPriority::done());
+    }
+}
@@ -0,0 +1,30 @@
+use serde_derive::{Deserialize, Serialize};
+
+// The mutation strategy that produced an input, recorded with the values taken from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Strategy {
+    #[default]
+    Seed,
+    Sync,
+    Reusing,
+    Gd,
+    Random,
+    Cbh,
+    Mb,
+    OneByte,
+    Det,
+    Exploit,
+    Afl,
+    Len,
+    CmpFn,
+    // tokens of a user dictionary
+    Dict,
+}
+
+// Where the values of new records come from: the input that has just been tracked.
+pub struct RecordSource<'a> {
+    pub buf: &'a [u8],
+    pub strategy: Strategy,
+    // the input crashed the program
+    pub crash: bool,
+}
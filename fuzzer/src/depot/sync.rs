@@ -3,7 +3,7 @@ use crate::executor::Executor;
 use angora_common::{config, defs};
 use std::{
     collections::HashMap,
-    fs,
+    fs, mem,
     path::Path,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -45,6 +45,7 @@ pub fn sync_afl(
 ) {
     executor.rebind_forksrv();
     executor.local_stats.clear();
+    let strategy = mem::replace(&mut executor.strategy, Strategy::Sync);
 
     if let Ok(entries) = sync_dir.read_dir() {
         for entry in entries {
@@ -67,6 +68,7 @@ pub fn sync_afl(
 
     let n: usize = executor.local_stats.num_inputs.into();
     info!("sync {} file from AFL.", n);
+    executor.strategy = strategy;
 
     executor.update_log();
 }
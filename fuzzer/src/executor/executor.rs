@@ -5,10 +5,11 @@ use crate::{
     cond_stmt::{self, NextState},
     depot, stats, track,
 };
-use angora_common::{config, defs};
+use angora_common::{config, defs, tag::TagSeg};
 
 use std::{
     collections::{HashMap, HashSet},
+    fs,
     path::Path,
     process::{Command, Stdio},
     sync::{
@@ -31,9 +32,13 @@ pub struct Executor {
     invariable_cnt: usize,
     pub last_f: u64,
     pub has_new_path: bool,
+    // inputs of new paths found so far
+    pub num_new_paths: usize,
     pub global_stats: Arc<RwLock<stats::ChartStats>>,
     pub local_stats: stats::LocalStats,
     pub current_mutated_offsets: HashSet<u32>,
+    // strategy of the current executions, stored with the records they produce
+    pub strategy: depot::Strategy,
 }
 
 impl Executor {
@@ -94,9 +99,11 @@ impl Executor {
             invariable_cnt: 0,
             last_f: defs::UNREACHABLE,
             has_new_path: false,
+            num_new_paths: 0,
             global_stats,
             local_stats: Default::default(),
             current_mutated_offsets: HashSet::new(),
+            strategy: Default::default(),
         }
     }
 
@@ -205,6 +212,32 @@ impl Executor {
         (status, output)
     }
 
+    // Same as `run_with_cond` for values copied from elsewhere, e.g. by reusing.
+    // A cond they solve is marked as done, but the consistency and invariable output
+    // checks, which are meant for the search's own steps, are left alone.
+    pub fn run_with_cond_copied(
+        &mut self,
+        buf: &Vec<u8>,
+        cond: &mut cond_stmt::CondStmt,
+    ) -> (StatusType, u64) {
+        self.run_init();
+        self.t_conds.set(cond);
+        let mut status = self.run_inner(buf);
+
+        let output = self.t_conds.get_cond_output();
+        let mut explored = false;
+        let skip = self.check_explored(cond, status, output, &mut explored);
+
+        self.do_if_has_new(buf, status, explored, cond.base.cmpid);
+        status = self.check_timeout(status, cond);
+
+        if skip {
+            status = StatusType::Skip;
+        }
+
+        (status, output)
+    }
+
     fn try_unlimited_memory(&mut self, buf: &Vec<u8>, cmpid: u32) -> bool {
         let mut skip = false;
         self.branches.clear_trace();
@@ -237,6 +270,7 @@ impl Executor {
 
         if has_new_path {
             self.has_new_path = true;
+            self.num_new_paths += 1;
             self.local_stats.find_new(&status);
             let id = self.depot.save(status, &buf, cmpid);
 
@@ -258,16 +292,23 @@ impl Executor {
                 if !crash_or_tmout {
                     let cond_stmts = self.track(id, buf, speed);
                     if cond_stmts.len() > 0 {
+                        let source = depot::RecordSource {
+                            buf,
+                            strategy: self.strategy,
+                            crash: false,
+                        };
                         // Filter cond_stmts based on mutated offsets
-                        self.depot.add_entries_with_filter(cond_stmts, &self.current_mutated_offsets);
+                        self.depot.add_entries_with_filter(cond_stmts, &self.current_mutated_offsets, &source);
                         if self.cmd.enable_afl {
-                            self.depot
-                                .add_entries(vec![cond_stmt::CondStmt::get_afl_cond(
-                                    id, speed, edge_num,
-                                )]);
+                            self.depot.add_entries(
+                                vec![cond_stmt::CondStmt::get_afl_cond(id, speed, edge_num)],
+                                &source,
+                            );
                         }
                     }
                 }
+            } else if status == StatusType::Crash && config::ENABLE_CRASH_TRACK {
+                self.track_crash(id, buf);
             }
         }
     }
@@ -285,6 +326,18 @@ impl Executor {
         self.do_if_has_new(buf, status, false, 0);
     }
 
+    // Run `buf` without saving it. Returns its status, its path and the time used (us).
+    pub fn run_path(&mut self, buf: &Vec<u8>) -> (StatusType, Vec<(usize, u8)>, u32) {
+        self.run_init();
+        let t_start = time::Instant::now();
+        let status = self.run_inner(buf);
+        let used_us = t_start.elapsed().as_micros() as u32;
+        if status == StatusType::Error {
+            self.rebind_forksrv();
+        }
+        (status, self.branches.get_path(), used_us)
+    }
+
     fn run_init(&mut self) {
         self.has_new_path = false;
         self.local_stats.num_exec.count();
@@ -348,14 +401,12 @@ impl Executor {
         used_us / 3
     }
 
-    fn track(&mut self, id: usize, buf: &Vec<u8>, speed: u32) -> Vec<cond_stmt::CondStmt> {
+    fn run_track(&mut self, buf: &Vec<u8>) -> StatusType {
         self.envs.insert(
             defs::TRACK_OUTPUT_VAR.to_string(),
             self.cmd.track_path.clone(),
         );
 
-        let t_now: stats::TimeIns = Default::default();
-
         self.write_test(buf);
 
         compiler_fence(Ordering::SeqCst);
@@ -366,7 +417,30 @@ impl Executor {
             config::TIME_LIMIT_TRACK,
         );
         compiler_fence(Ordering::SeqCst);
+        ret_status
+    }
+
+    // Like run_track, but the runtime also keeps the log of an input that crashes.
+    fn run_track_crashing(&mut self, buf: &Vec<u8>) -> StatusType {
+        self.envs.insert(defs::TRACK_CRASH_VAR.to_string(), String::from("TRUE"));
+        let ret_status = self.run_track(buf);
+        self.envs.remove(defs::TRACK_CRASH_VAR);
+        ret_status
+    }
+
+    fn load_track(&self, id: usize, speed: u32) -> Vec<cond_stmt::CondStmt> {
+        track::load_track_data(
+            Path::new(&self.cmd.track_path),
+            id as u32,
+            speed,
+            self.cmd.mode.is_pin_mode(),
+            self.cmd.enable_exploitation,
+        )
+    }
 
+    fn track(&mut self, id: usize, buf: &Vec<u8>, speed: u32) -> Vec<cond_stmt::CondStmt> {
+        let t_now: stats::TimeIns = Default::default();
+        let ret_status = self.run_track(buf);
         if ret_status != StatusType::Normal {
             error!(
                 "Crash or hang while tracking! -- {:?},  id: {}",
@@ -375,18 +449,55 @@ impl Executor {
             return vec![];
         }
 
-        let cond_list = track::load_track_data(
-            Path::new(&self.cmd.track_path),
-            id as u32,
-            speed,
-            self.cmd.mode.is_pin_mode(),
-            self.cmd.enable_exploitation,
-        );
-
+        let cond_list = self.load_track(id, speed);
         self.local_stats.track_time += t_now.into();
         cond_list
     }
 
+    // Track a crashing input. The runtime writes the conds logged before the crash,
+    // whose values are recorded for reusing.
+    fn track_crash(&mut self, id: usize, buf: &Vec<u8>) {
+        let t_now: stats::TimeIns = Default::default();
+        // never read the log of an earlier input if the crash happens before any cond
+        if let Err(e) = fs::File::create(&self.cmd.track_path) {
+            warn!("Could not reset the track file: {:?}", e);
+            return;
+        }
+        let ret_status = self.run_track_crashing(buf);
+        if ret_status == StatusType::Timeout {
+            warn!("Hang while tracking crash id: {}", id);
+            return;
+        }
+
+        let cond_list = self.load_track(defs::CRASH_BELONG as usize, 0);
+        self.local_stats.track_time += t_now.into();
+        if !cond_list.is_empty() {
+            let source = depot::RecordSource {
+                buf,
+                strategy: self.strategy,
+                crash: true,
+            };
+            depot::add_crash_conds(&cond_list, &source);
+        }
+    }
+
+    // Input offsets the conds of `buf` depend on, as logged up to its end or crash.
+    // Empty if the track binary hangs.
+    pub fn tainted_offsets(&mut self, buf: &Vec<u8>) -> Vec<TagSeg> {
+        if let Err(e) = fs::File::create(&self.cmd.track_path) {
+            warn!("Could not reset the track file: {:?}", e);
+            return vec![];
+        }
+        if self.run_track_crashing(buf) == StatusType::Timeout {
+            warn!("Hang while tracking, no taint information");
+            return vec![];
+        }
+        self.load_track(0, 0)
+            .into_iter()
+            .flat_map(|cond| cond.offsets.into_iter().chain(cond.offsets_opt))
+            .collect()
+    }
+
     pub fn random_input_buf(&self) -> Vec<u8> {
         let id = self.depot.next_random();
         self.depot.get_input_buf(id)
@@ -450,7 +561,11 @@ impl Executor {
             .write()
             .unwrap()
             .sync_from_local(&mut self.local_stats);
+        self.end_round();
+    }
 
+    // Forget the state of the cond's round, without counting it in the stats.
+    pub fn end_round(&mut self) {
         self.t_conds.clear();
         self.tmout_cnt = 0;
         self.invariable_cnt = 0;
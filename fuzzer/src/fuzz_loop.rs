@@ -1,5 +1,5 @@
 use crate::{
-    branches::GlobalBranches, command::CommandOpt, cond_stmt::NextState, depot::Depot,
+    branches::GlobalBranches, command::CommandOpt, cond_stmt::NextState, depot::{Depot, Strategy},
     executor::Executor, fuzz_type::FuzzType, search::*, stats,
 };
 use rand::prelude::*;
@@ -17,6 +17,7 @@ pub fn fuzz_loop(
     global_stats: Arc<RwLock<stats::ChartStats>>,
 ) {
     let search_method = cmd_opt.search_method;
+    let reusing_opt = cmd_opt.reusing;
     let mut executor = Executor::new(
         cmd_opt,
         global_branches,
@@ -64,13 +65,15 @@ pub fn fuzz_loop(
         */
 
         let buf = depot.get_input_buf(belong_input);
+        let fuzz_type = cond.get_fuzz_type();
+        let num_new_paths = executor.num_new_paths;
 
         {
-            let fuzz_type = cond.get_fuzz_type();
             let mut handler = SearchHandler::new(running.clone(), &mut executor, &mut cond, buf);
             match fuzz_type {
                 FuzzType::ExploreFuzz => {
-                    let solved_by_reusing = apply_reusing_mutation(&mut handler, 50);
+                    let solved_by_reusing =
+                        reusing_opt.runs_before() && apply_reusing_mutation(&mut handler, &reusing_opt);
 
                     if solved_by_reusing {
                         info!("[FuzzLoop] Condition solved by reusing, skipping other mutations");
@@ -82,21 +85,27 @@ pub fn fuzz_loop(
                         }
             
                         if handler.cond.state.is_one_byte() {
+                            handler.executor.strategy = Strategy::OneByte;
                             OneByteFuzz::new(handler).run();
                         } else if handler.cond.state.is_det() {
+                            handler.executor.strategy = Strategy::Det;
                             DetFuzz::new(handler).run();
                         } else {
                             match search_method {
                                 SearchMethod::Gd => {
+                                    handler.executor.strategy = Strategy::Gd;
                                     GdSearch::new(handler).run(&mut thread_rng());
                                 },
                                 SearchMethod::Random => {
+                                    handler.executor.strategy = Strategy::Random;
                                     RandomSearch::new(handler).run();
                                 },
                                 SearchMethod::Cbh => {
+                                    handler.executor.strategy = Strategy::Cbh;
                                     CbhSearch::new(handler).run();
                                 },
                                 SearchMethod::Mb => {
+                                    handler.executor.strategy = Strategy::Mb;
                                     MbSearch::new(handler).run();
                                 },
                             }
@@ -104,25 +113,31 @@ pub fn fuzz_loop(
                     }
                 },
                 FuzzType::ExploitFuzz => {
-                    let solved_by_reusing = apply_reusing_mutation(&mut handler, 50);
+                    let solved_by_reusing =
+                        reusing_opt.runs_before() && apply_reusing_mutation(&mut handler, &reusing_opt);
             
                     if !solved_by_reusing {
                         if handler.cond.state.is_one_byte() {
+                            handler.executor.strategy = Strategy::OneByte;
                             let mut fz = OneByteFuzz::new(handler);
                             fz.run();
                             fz.handler.cond.to_unsolvable();
                         } else {
+                            handler.executor.strategy = Strategy::Exploit;
                             ExploitFuzz::new(handler).run();
                         }
                     }
                 },
                 FuzzType::AFLFuzz => {
+                    handler.executor.strategy = Strategy::Afl;
                     AFLFuzz::new(handler).run();
                 },
                 FuzzType::LenFuzz => {
+                    handler.executor.strategy = Strategy::Len;
                     LenFuzz::new(handler).run();
                 },
                 FuzzType::CmpFnFuzz => {
+                    handler.executor.strategy = Strategy::CmpFn;
                     FnFuzz::new(handler).run();
                 },
                 FuzzType::OtherFuzz => {
@@ -131,6 +146,14 @@ pub fn fuzz_loop(
             }
         }
 
+        let is_search = matches!(fuzz_type, FuzzType::ExploreFuzz | FuzzType::ExploitFuzz);
+        if reusing_opt.runs_after() && is_search && !cond.is_done() {
+            let buf = depot.get_input_buf(belong_input);
+            let mut handler = SearchHandler::resume(running.clone(), &mut executor, &mut cond, buf);
+            apply_reusing_mutation(&mut handler, &reusing_opt);
+        }
+
+        cond.recent_gain = executor.num_new_paths - num_new_paths;
         depot.update_entry(cond);
     }
 }
@@ -13,29 +13,76 @@ use std::{
     thread, time,
 };
 
-use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, stats};
+use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, search, session, stats};
 use ctrlc;
 use libc;
 use pretty_env_logger;
 
-pub fn fuzz_main(
-    mode: &str,
-    in_dir: &str,
-    out_dir: &str,
-    track_target: &str,
-    pargs: Vec<String>,
-    bind: Option<usize>,
-    num_jobs: usize,
-    mem_limit: u64,
-    time_limit: u64,
-    search_method: &str,
-    sync_afl: bool,
-    enable_afl: bool,
-    enable_exploitation: bool,
-) {
+// Checkpoint the label pattern map and the session, and export the dictionary every 12 * 5 seconds.
+const PATTERN_DB_CHECKPOINT_TICKS: usize = 12;
+
+// Options of a fuzzing session, as given on the command line.
+pub struct FuzzOpt<'a> {
+    pub mode: &'a str,
+    pub in_dir: &'a str,
+    pub out_dir: &'a str,
+    pub track_target: &'a str,
+    pub pargs: Vec<String>,
+    pub bind: Option<usize>,
+    pub num_jobs: usize,
+    pub mem_limit: u64,
+    pub time_limit: u64,
+    pub search_method: &'a str,
+    pub sync_afl: bool,
+    pub enable_afl: bool,
+    pub enable_exploitation: bool,
+    pub pattern_db: Option<&'a str>,
+    pub import_dbs: Vec<String>,
+    pub dicts: Vec<String>,
+    pub max_records_per_pattern: usize,
+    pub max_pattern_records: usize,
+    pub pattern_eviction: &'a str,
+    pub enable_reusing: bool,
+    pub reusing_stage: &'a str,
+    pub reusing_order: &'a str,
+    pub reusing_budget: &'a str,
+    pub reusing_relocate: bool,
+    pub schedule: &'a str,
+    pub resume: bool,
+}
+
+pub fn fuzz_main(opt: FuzzOpt) {
+    let FuzzOpt {
+        mode,
+        in_dir,
+        out_dir,
+        track_target,
+        pargs,
+        bind,
+        num_jobs,
+        mem_limit,
+        time_limit,
+        search_method,
+        sync_afl,
+        enable_afl,
+        enable_exploitation,
+        pattern_db,
+        import_dbs,
+        dicts,
+        max_records_per_pattern,
+        max_pattern_records,
+        pattern_eviction,
+        enable_reusing,
+        reusing_stage,
+        reusing_order,
+        reusing_budget,
+        reusing_relocate,
+        schedule,
+        resume,
+    } = opt;
     pretty_env_logger::init();
 
-    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_afl);
+    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_afl, resume);
     let command_option = command::CommandOpt::new(
         mode,
         track_target,
@@ -46,15 +93,58 @@ pub fn fuzz_main(
         time_limit,
         enable_afl,
         enable_exploitation,
+        search::ReusingOpt::new(
+            enable_reusing,
+            reusing_stage,
+            reusing_order,
+            reusing_budget,
+            reusing_relocate,
+        ),
     );
     info!("{:?}", command_option);
 
     check_dep::check_dep(in_dir, out_dir, &command_option);
 
-    let depot = Arc::new(depot::Depot::new(seeds_dir, &angora_out_dir));
+    let depot = Arc::new(depot::Depot::new(
+        seeds_dir,
+        &angora_out_dir,
+        depot::SchedulePolicy::from(schedule),
+    ));
     info!("{:?}", depot.dirs);
 
-    let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
+    depot::set_pattern_limits(depot::PatternLimits {
+        max_per_pattern: max_records_per_pattern,
+        max_total: max_pattern_records,
+        eviction: depot::EvictionPolicy::from(pattern_eviction),
+    });
+    if resume {
+        if let Err(e) = session::load_session_patterns(&angora_out_dir) {
+            error!("FATAL: Could not load the pattern db of the session in {:?}: {:?}", angora_out_dir, e);
+            panic!();
+        }
+    }
+    if let Some(db_path) = pattern_db {
+        if let Err(e) = depot::load_from_db(Path::new(db_path)) {
+            error!("FATAL: Could not load pattern db {:?}: {:?}", db_path, e);
+            panic!();
+        }
+    }
+    for db_path in &import_dbs {
+        if let Err(e) = depot::import_db(Path::new(db_path)) {
+            error!("FATAL: Could not import pattern db {:?}: {:?}", db_path, e);
+            panic!();
+        }
+    }
+    if !dicts.is_empty() {
+        if let Err(e) = depot::load_dicts(&dicts) {
+            error!("FATAL: Could not load dictionary {:?}: {:?}", dicts, e);
+            panic!();
+        }
+    }
+
+    let mut chart_stats = stats::ChartStats::new();
+    chart_stats.set_reusing_opt(command_option.reusing);
+    let stats = Arc::new(RwLock::new(chart_stats));
     let global_branches = Arc::new(branches::GlobalBranches::new());
     let fuzzer_stats = create_stats_file_and_write_pid(&angora_out_dir);
     let running = Arc::new(AtomicBool::new(true));
@@ -67,7 +157,14 @@ pub fn fuzz_main(
         stats.clone(),
     );
 
-    depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    if resume {
+        if let Err(e) = session::load_session(&angora_out_dir, &depot, &global_branches, &stats) {
+            error!("FATAL: Could not resume the session in {:?}: {:?}", angora_out_dir, e);
+            panic!();
+        }
+    } else {
+        depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    }
 
     if depot.empty() {
         error!("Failed to find any branches during dry run.");
@@ -89,7 +186,14 @@ pub fn fuzz_main(
         &stats,
     );
 
-    let log_file = match fs::File::create(angora_out_dir.join(defs::ANGORA_LOG_FILE)) {
+    // a resumed session goes on with its log
+    let log_file = match fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resume)
+        .truncate(!resume)
+        .open(angora_out_dir.join(defs::ANGORA_LOG_FILE))
+    {
         Ok(a) => a,
         Err(e) => {
             error!("FATAL: Could not create log file: {:?}", e);
@@ -116,8 +220,9 @@ pub fn fuzz_main(
 
     info!("Fuzzing finished. Saving results...");
 
-    // Pattern map 저장 (3가지 형식)
-    let pattern_text = angora_out_dir.join("label_patterns.txt");
+    // Pattern map 저장 (text + db)
+    let pattern_text = angora_out_dir.join(defs::LABEL_PATTERN_FILE);
+    let pattern_db = angora_out_dir.join(defs::LABEL_PATTERN_DB_FILE);
 
     // 최종 통계 출력
     depot::print_pattern_stats();
@@ -126,6 +231,15 @@ pub fn fuzz_main(
     if let Err(e) = depot::save_to_text(&pattern_text) {
         warn!("Failed to save pattern map (text): {:?}", e);
     }
+    if let Err(e) = depot::save_to_db(&pattern_db) {
+        warn!("Failed to save pattern map (db): {:?}", e);
+    }
+    if let Err(e) = depot::export_dict(&angora_out_dir.join(defs::DICT_FILE), &depot) {
+        warn!("Failed to export dictionary: {:?}", e);
+    }
+    if let Err(e) = session::save_session(&angora_out_dir, &depot, &global_branches, &stats) {
+        warn!("Failed to save session: {:?}", e);
+    }
 
     info!("Pattern map saved successfully!");
 
@@ -147,7 +261,7 @@ pub fn fuzz_main(
     };
 }
 
-fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathBuf, PathBuf) {
+fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool, resume: bool) -> (PathBuf, PathBuf) {
     let angora_out_dir = if sync_afl {
         gen_path_afl(out_dir)
     } else {
@@ -155,6 +269,15 @@ fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathB
     };
 
     let restart = in_dir == "-";
+    if resume {
+        // go on in the output directory from its last checkpoint
+        if !restart || !angora_out_dir.join(defs::SESSION_DIR).is_dir() {
+            error!("FATAL: --resume needs -i - and an output directory with a session checkpoint");
+            panic!();
+        }
+        let seeds_dir = angora_out_dir.join(defs::INPUTS_DIR);
+        return (seeds_dir, angora_out_dir);
+    }
     if !restart {
         fs::create_dir(&angora_out_dir).expect("Output directory has existed!");
     }
@@ -267,6 +390,14 @@ fn main_thread_sync_and_log(
         depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
     }
     let mut sync_counter = 1;
+    let mut checkpoint_counter = PATTERN_DB_CHECKPOINT_TICKS;
+    let angora_out_dir = depot
+        .dirs
+        .inputs_dir
+        .parent()
+        .expect("Could not get parent directory.");
+    let pattern_db = angora_out_dir.join(defs::LABEL_PATTERN_DB_FILE);
+    let dict = pattern_db.with_file_name(defs::DICT_FILE);
     show_stats(&mut log_file, depot, global_branches, stats);
     while running.load(Ordering::SeqCst) {
         thread::sleep(time::Duration::from_secs(5));
@@ -276,6 +407,20 @@ fn main_thread_sync_and_log(
             sync_counter = 12;
         }
 
+        checkpoint_counter -= 1;
+        if checkpoint_counter == 0 {
+            if let Err(e) = depot::save_to_db(&pattern_db) {
+                warn!("Failed to checkpoint pattern map: {:?}", e);
+            }
+            if let Err(e) = depot::export_dict(&dict, depot) {
+                warn!("Failed to export dictionary: {:?}", e);
+            }
+            if let Err(e) = session::save_session(angora_out_dir, depot, global_branches, stats) {
+                warn!("Failed to checkpoint session: {:?}", e);
+            }
+            checkpoint_counter = PATTERN_DB_CHECKPOINT_TICKS;
+        }
+
         show_stats(&mut log_file, depot, global_branches, stats);
         if Arc::strong_count(&child_count) == 1 {
             let s = stats.read().unwrap();
@@ -7,7 +7,7 @@ extern crate derive_more;
 
 mod branches;
 pub mod cond_stmt;
-mod depot;
+pub mod depot;
 pub mod executor;
 mod mut_input;
 mod search;
@@ -17,10 +17,18 @@ pub mod track;
 mod fuzz_loop;
 mod fuzz_main;
 mod fuzz_type;
+mod session;
+mod cmin;
+mod standalone;
+mod tmin;
 
 mod bind_cpu;
 mod check_dep;
 mod command;
 mod tmpfs;
 
-pub use crate::fuzz_main::fuzz_main;
+pub use crate::{
+    cmin::cmin_main,
+    fuzz_main::{fuzz_main, FuzzOpt},
+    tmin::tmin_main,
+};
@@ -0,0 +1,89 @@
+// Adapt a stored critical value to a slot of a different shape.
+// Values are raw input bytes, so the endianness decides which end holds the high bytes.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+// Widths of the single segment patterns a value can be adapted from.
+pub static ADAPT_WIDTHS: [u32; 4] = [1, 2, 4, 8];
+
+fn is_negative(value: &[u8], endian: Endian) -> bool {
+    let high = match endian {
+        Endian::Little => value.last(),
+        Endian::Big => value.first(),
+    };
+    high.is_some_and(|&b| b & 0x80 > 0)
+}
+
+// Sign- or zero-extend `value` to `width` bytes, or truncate it to the low `width` bytes.
+pub fn adapt_value(value: &[u8], width: usize, signed: bool, endian: Endian) -> Vec<u8> {
+    let len = value.len();
+    if width <= len {
+        return match endian {
+            Endian::Little => value[..width].to_vec(),
+            Endian::Big => value[len - width..].to_vec(),
+        };
+    }
+
+    let fill = if signed && is_negative(value, endian) {
+        0xFF
+    } else {
+        0
+    };
+    let padding = vec![fill; width - len];
+    match endian {
+        Endian::Little => [value, &padding[..]].concat(),
+        Endian::Big => [&padding[..], value].concat(),
+    }
+}
+
+pub fn swap_bytes(value: &[u8]) -> Vec<u8> {
+    value.iter().rev().cloned().collect()
+}
+
+// Re-partition one value into consecutive slots of the given widths.
+pub fn split_value(value: &[u8], widths: &[u32]) -> Option<Vec<Vec<u8>>> {
+    let total: usize = widths.iter().map(|&w| w as usize).sum();
+    if total != value.len() {
+        return None;
+    }
+
+    let mut begin = 0;
+    let mut values = Vec::with_capacity(widths.len());
+    for &w in widths {
+        let end = begin + w as usize;
+        values.push(value[begin..end].to_vec());
+        begin = end;
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapt_value() {
+        // zero extend
+        assert_eq!(adapt_value(&[0x34, 0x12], 4, false, Endian::Little), vec![0x34, 0x12, 0, 0]);
+        assert_eq!(adapt_value(&[0x12, 0x34], 4, false, Endian::Big), vec![0, 0, 0x12, 0x34]);
+        // sign extend
+        assert_eq!(adapt_value(&[0xfe, 0xff], 4, true, Endian::Little), vec![0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(adapt_value(&[0xff, 0xfe], 4, true, Endian::Big), vec![0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(adapt_value(&[0x7f], 2, true, Endian::Little), vec![0x7f, 0]);
+        // truncate
+        assert_eq!(adapt_value(&[1, 2, 3, 4], 2, false, Endian::Little), vec![1, 2]);
+        assert_eq!(adapt_value(&[1, 2, 3, 4], 2, false, Endian::Big), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_split_value() {
+        assert_eq!(split_value(&[1, 2, 3, 4], &[2, 2]), Some(vec![vec![1, 2], vec![3, 4]]));
+        assert_eq!(split_value(&[1, 2, 3, 4], &[1, 3]), Some(vec![vec![1], vec![2, 3, 4]]));
+        assert_eq!(split_value(&[1, 2, 3], &[2, 2]), None);
+        assert_eq!(swap_bytes(&[1, 2, 3, 4]), vec![4, 3, 2, 1]);
+    }
+}
@@ -4,9 +4,12 @@
 // And GE algorithm.
 
 use super::*;
+use crate::depot::dict_tokens;
 use rand::{self, distributions::Uniform, Rng};
 
 static IDX_TO_SIZE: [usize; 4] = [1, 2, 4, 8];
+// One in DICT_HAVOC_RATIO stacked mutations puts a dictionary token, if there are any.
+const DICT_HAVOC_RATIO: u32 = 4;
 
 pub struct AFLFuzz<'a> {
     handler: SearchHandler<'a>,
@@ -141,8 +144,27 @@ impl<'a> AFLFuzz<'a> {
         let mut byte_len = buf.len() as u32;
         let use_stacking = 1 + rng.gen_range(0, max_stacking);
         let mut mutated_offsets = Vec::new();
+        let tokens = dict_tokens();
 
         for _ in 0..use_stacking {
+            if !tokens.is_empty() && rng.gen_range(0, DICT_HAVOC_RATIO) == 0 {
+                let token = &tokens[rng.gen_range(0, tokens.len())];
+                let token_len = token.len() as u32;
+                let insert = config::ENABLE_MICRO_RANDOM_LEN && rng.gen();
+                if insert && byte_len + token_len < config::MAX_INPUT_LEN as u32 {
+                    // insert token
+                    let byte_idx: u32 = rng.gen_range(0, byte_len + 1);
+                    buf.splice(byte_idx as usize..byte_idx as usize, token.iter().cloned());
+                    byte_len += token_len;
+                    mutated_offsets.extend(byte_idx..byte_idx + token_len);
+                } else if !insert && byte_len >= token_len {
+                    // overwrite with token
+                    let byte_idx: u32 = rng.gen_range(0, byte_len - token_len + 1);
+                    buf[byte_idx as usize..(byte_idx + token_len) as usize].copy_from_slice(token);
+                    mutated_offsets.extend(byte_idx..byte_idx + token_len);
+                }
+                continue;
+            }
             match rng.sample(choice_range) {
                 0 | 1 => {
                     // flip bit
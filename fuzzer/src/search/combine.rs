@@ -0,0 +1,104 @@
+// Enumerate the Cartesian product of the segment pools in a fixed order.
+// Pools are sorted best first, so tuples are ordered by the sum of their indexes:
+// combinations of the best ranked values come first, and every tuple is visited exactly once.
+
+pub struct Combinations {
+    bounds: Vec<usize>,
+    level: usize,
+    max_level: usize,
+    current: Option<Vec<usize>>,
+}
+
+impl Combinations {
+    pub fn new(pool_sizes: &[usize]) -> Self {
+        let empty = pool_sizes.is_empty() || pool_sizes.contains(&0);
+        let bounds: Vec<usize> = pool_sizes.iter().map(|&s| s.saturating_sub(1)).collect();
+        let max_level = bounds.iter().sum();
+        let current = if empty { None } else { fill_from_end(&bounds, 0) };
+        Self {
+            bounds,
+            level: 0,
+            max_level,
+            current,
+        }
+    }
+
+    // The next tuple with the same sum of indexes, in lexicographic order.
+    fn next_in_level(&self, t: &[usize]) -> Option<Vec<usize>> {
+        let n = t.len();
+        let mut suffix = 0;
+        for i in (0..n.saturating_sub(1)).rev() {
+            suffix += t[i + 1];
+            if t[i] < self.bounds[i] && suffix > 0 {
+                let mut next = t[..=i].to_vec();
+                next[i] += 1;
+                next.extend(fill_from_end(&self.bounds[i + 1..], suffix - 1)?);
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+// The smallest tuple in lexicographic order within `bounds` whose indexes sum to `sum`.
+fn fill_from_end(bounds: &[usize], mut sum: usize) -> Option<Vec<usize>> {
+    let mut t = vec![0; bounds.len()];
+    for j in (0..bounds.len()).rev() {
+        t[j] = bounds[j].min(sum);
+        sum -= t[j];
+    }
+    if sum > 0 {
+        None
+    } else {
+        Some(t)
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let cur = self.current.take()?;
+        self.current = match self.next_in_level(&cur) {
+            Some(next) => Some(next),
+            None if self.level < self.max_level => {
+                self.level += 1;
+                fill_from_end(&self.bounds, self.level)
+            },
+            None => None,
+        };
+        Some(cur)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combinations() {
+        let all: Vec<Vec<usize>> = Combinations::new(&[2, 3]).collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![0, 0],
+                vec![0, 1],
+                vec![1, 0],
+                vec![0, 2],
+                vec![1, 1],
+                vec![1, 2],
+            ]
+        );
+
+        let all: Vec<Vec<usize>> = Combinations::new(&[3, 1, 4]).collect();
+        assert_eq!(all.len(), 12);
+        let mut sorted = all.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 12);
+        assert!(all.windows(2).all(|w| w[0].iter().sum::<usize>() <= w[1].iter().sum::<usize>()));
+
+        assert_eq!(Combinations::new(&[2, 0]).count(), 0);
+        assert_eq!(Combinations::new(&[]).count(), 0);
+    }
+}
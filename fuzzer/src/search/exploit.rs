@@ -1,4 +1,5 @@
 use super::*;
+use crate::depot::dict_tokens;
 
 pub struct ExploitFuzz<'a> {
     handler: SearchHandler<'a>,
@@ -32,8 +33,28 @@ impl<'a> ExploitFuzz<'a> {
                     input.set(i, v);
                     self.handler.execute_input(&input);
                 }
+                // and dictionary tokens of the same size
+                for token in dict_tokens().iter().filter(|t| t.len() == n) {
+                    if self.handler.is_stopped_or_skip() {
+                        break;
+                    }
+                    if let Ok(v) = mut_input::read_val_from_buf(token, 0, n) {
+                        input.set(i, v);
+                        self.handler.execute_input(&input);
+                    }
+                }
                 input.set(i, orig_v);
             }
+
+            // tokens covering all the bytes of the cond
+            let val_len = input.val_len();
+            for token in dict_tokens().iter().filter(|t| t.len() == val_len) {
+                if self.handler.is_stopped_or_skip() {
+                    break;
+                }
+                input.assign(token);
+                self.handler.execute_input(&input);
+            }
         }
 
         self.handler.max_times = config::MAX_EXPLOIT_EXEC_NUM.into();
@@ -10,6 +10,8 @@ pub struct SearchHandler<'a> {
     pub max_times: Counter,
     pub skip: bool,
     pub mutated_offsets: HashSet<u32>,
+    // the round is counted in the stats when the handler is dropped
+    counts_round: bool,
 }
 
 impl<'a> SearchHandler<'a> {
@@ -19,8 +21,29 @@ impl<'a> SearchHandler<'a> {
         cond: &'a mut CondStmt,
         buf: Vec<u8>,
     ) -> Self {
-        executor.local_stats.register(cond);
         cond.fuzz_times = cond.fuzz_times + 1;
+        executor.local_stats.register(cond);
+        Self::with_round(running, executor, cond, buf, true)
+    }
+
+    // Continue the current round of the cond, e.g. for reusing after the search.
+    // The round has been counted by the first handler, so this one is not.
+    pub fn resume(
+        running: Arc<AtomicBool>,
+        executor: &'a mut Executor,
+        cond: &'a mut CondStmt,
+        buf: Vec<u8>,
+    ) -> Self {
+        Self::with_round(running, executor, cond, buf, false)
+    }
+
+    fn with_round(
+        running: Arc<AtomicBool>,
+        executor: &'a mut Executor,
+        cond: &'a mut CondStmt,
+        buf: Vec<u8>,
+        counts_round: bool,
+    ) -> Self {
         Self {
             running,
             executor,
@@ -29,6 +52,7 @@ impl<'a> SearchHandler<'a> {
             max_times: config::MAX_SEARCH_EXEC_NUM.into(),
             skip: false,
             mutated_offsets: HashSet::new(),
+            counts_round,
         }
     }
 
@@ -106,6 +130,14 @@ impl<'a> SearchHandler<'a> {
         f_output
     }
 
+    // Run the buffer holding values copied from elsewhere, see `run_with_cond_copied`.
+    pub fn execute_cond_copied(&mut self) -> u64 {
+        self.executor.set_mutated_offsets(self.mutated_offsets.clone());
+        let (status, f_output) = self.executor.run_with_cond_copied(&self.buf, self.cond);
+        self.process_status(status);
+        f_output
+    }
+
     pub fn execute_input_direct(&mut self) {
         self.executor.set_mutated_offsets(self.mutated_offsets.clone());
         let status = self.executor.run(&self.buf, self.cond);
@@ -120,6 +152,10 @@ impl<'a> SearchHandler<'a> {
 
 impl<'a> Drop for SearchHandler<'a> {
     fn drop(&mut self) {
-        self.executor.update_log();
+        if self.counts_round {
+            self.executor.update_log();
+        } else {
+            self.executor.end_round();
+        }
     }
 }
@@ -1,6 +1,9 @@
 // Assume it is direct and linear
 use super::*;
-use crate::cond_stmt::CondOutput;
+use crate::{
+    cond_stmt::CondOutput,
+    depot::{fix_length_fields, length_fields, LengthField},
+};
 
 pub struct LenFuzz<'a> {
     handler: SearchHandler<'a>,
@@ -11,6 +14,19 @@ impl<'a> LenFuzz<'a> {
         Self { handler }
     }
 
+    // Run the resized input again with its known length fields updated to the new size.
+    fn execute_with_length_fields(&mut self, buf: &[u8], fields: &[LengthField]) {
+        let mut fixed = buf.to_vec();
+        let changed = fix_length_fields(&mut fixed, fields);
+        if changed.is_empty() {
+            return;
+        }
+        for offset in changed {
+            self.handler.record_mutated_offset(offset);
+        }
+        self.handler.execute(&fixed);
+    }
+
     pub fn run(&mut self) {
         if !config::ENABLE_INPUT_LEN_EXPLORATION {
             self.handler.cond.mark_as_done();
@@ -26,6 +42,7 @@ impl<'a> LenFuzz<'a> {
         let size = self.handler.cond.base.lb2 as usize;
         let delta = self.handler.cond.base.get_output() as usize;
         let mut buf = self.handler.buf.clone();
+        let fields = length_fields();
         debug!(
             "len: delta {}, size: {}, buf_len: {}",
             delta,
@@ -48,6 +65,7 @@ impl<'a> LenFuzz<'a> {
                         self.handler.record_mutated_offset(i as u32);
                     }
                     self.handler.execute(&buf);
+                    self.execute_with_length_fields(&buf, &fields);
                     // some special chars: NULL, LF, CR, SPACE
                     let special_chars = vec![0, 10, 13, 32];
                     for c in special_chars {
@@ -74,6 +92,7 @@ impl<'a> LenFuzz<'a> {
                         self.handler.record_mutated_offset(i as u32);
                     }
                     self.handler.execute(&buf);
+                    self.execute_with_length_fields(&buf, &fields);
                     // len < X
                     if buf_len > extended_len + 1 {
                         let popped_idx = buf.len() - 1;
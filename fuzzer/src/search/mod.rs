@@ -45,5 +45,7 @@ pub mod det;
 pub use self::det::DetFuzz;
 pub mod one_byte;
 pub use self::one_byte::OneByteFuzz;
+pub mod adapt;
+pub mod combine;
 pub mod reusing;
-pub use self::reusing::apply_reusing_mutation;
\ No newline at end of file
+pub use self::reusing::{apply_reusing_mutation, ReusingOpt};
\ No newline at end of file
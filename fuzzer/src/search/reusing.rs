@@ -1,17 +1,160 @@
-use crate::depot::{LABEL_PATTERN_MAP, extract_pattern_merged, CondRecord, get_next_records};
-use crate::search::SearchHandler;
-use rand::seq::SliceRandom;
-use angora_common::tag::TagSeg;
+use crate::depot::{
+    LABEL_PATTERN_MAP, extract_pattern_merged, CondRecord, get_next_records,
+    get_next_imported_records, get_imported_source, update_record_outcome, RecordOutcome,
+    find_repartition_patterns, hash_values, Strategy, credit_record_shift, pattern_role,
+    encode_length, FieldRole,
+};
+use crate::search::{
+    adapt::{adapt_value, split_value, swap_bytes, Endian, ADAPT_WIDTHS},
+    combine::Combinations,
+    SearchHandler,
+};
+use angora_common::{config, tag::TagSeg};
 use crate::stats::REUSING_STATS;
+use serde_derive::Serialize;
+use std::{collections::HashSet, mem, time};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ReusingBudget {
+    #[default]
+    Fixed,
+    Adaptive,
+}
+
+pub fn parse_reusing_budget(b: &str) -> ReusingBudget {
+    match b {
+        "fixed" => ReusingBudget::Fixed,
+        "adaptive" => ReusingBudget::Adaptive,
+        _ => unreachable!(),
+    }
+}
+
+// Which stages of reusing to run.
+// Exact: records of the same pattern, local or imported. Combined: try_combined_segments only.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ReusingStage {
+    #[default]
+    All,
+    Exact,
+    Combined,
+}
+
+// Whether reusing runs before or after the gradient search of a cond.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ReusingOrder {
+    #[default]
+    Before,
+    After,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ReusingOpt {
+    pub enabled: bool,
+    pub stage: ReusingStage,
+    pub order: ReusingOrder,
+    pub budget: ReusingBudget,
+    // also write the values at the records' own offsets and shifts of them
+    pub relocate: bool,
+}
+
+impl ReusingOpt {
+    pub fn new(enabled: bool, stage: &str, order: &str, budget: &str, relocate: bool) -> Self {
+        let stage = match stage {
+            "all" => ReusingStage::All,
+            "exact" => ReusingStage::Exact,
+            "combined" => ReusingStage::Combined,
+            _ => unreachable!(),
+        };
+        let order = match order {
+            "before" => ReusingOrder::Before,
+            "after" => ReusingOrder::After,
+            _ => unreachable!(),
+        };
+        Self {
+            enabled,
+            stage,
+            order,
+            budget: parse_reusing_budget(budget),
+            relocate,
+        }
+    }
+
+    pub fn runs_before(&self) -> bool {
+        self.enabled && self.order == ReusingOrder::Before
+    }
+
+    pub fn runs_after(&self) -> bool {
+        self.enabled && self.order == ReusingOrder::After
+    }
+
+    fn runs_exact(&self) -> bool {
+        self.stage != ReusingStage::Combined
+    }
+
+    fn runs_adapted(&self) -> bool {
+        self.stage == ReusingStage::All
+    }
+
+    fn runs_relocated(&self) -> bool {
+        self.relocate && self.runs_exact()
+    }
+
+    fn runs_combined(&self) -> bool {
+        self.stage != ReusingStage::Exact
+    }
+}
+
+// More executions when reusing has been paying off, fewer for a cond that keeps coming back,
+// and never much more than the records available: the untried records of the pattern and the
+// untried combinations of its single segments.
+fn adaptive_iterations(num_records: usize, num_exec: usize, num_hits: usize, fuzz_times: usize) -> usize {
+    let rate = (num_hits as f64 + 1.0) / (num_exec + config::REUSING_PRIOR_EXEC) as f64;
+    let scale = (rate / config::REUSING_TARGET_RATE).min(1.0);
+    let range = config::MAX_REUSING_ITERATIONS - config::MIN_REUSING_ITERATIONS;
+    let iterations = config::MIN_REUSING_ITERATIONS + (range as f64 * scale) as usize;
+    (iterations / fuzz_times.max(1)).clamp(
+        config::MIN_REUSING_ITERATIONS,
+        num_records.max(config::MIN_REUSING_ITERATIONS),
+    )
+}
+
+fn get_iterations(budget: ReusingBudget, num_records: usize, fuzz_times: usize) -> usize {
+    match budget {
+        ReusingBudget::Fixed => config::REUSING_ITERATIONS,
+        ReusingBudget::Adaptive => {
+            let (num_exec, num_hits) = {
+                let stats = REUSING_STATS.lock().unwrap();
+                (
+                    stats.num_local_exec.0 + stats.num_imported_exec.0,
+                    stats.num_local_hits.0 + stats.num_imported_hits.0,
+                )
+            };
+            adaptive_iterations(num_records, num_exec, num_hits, fuzz_times)
+        },
+    }
+}
+
+// Combinations of the single segment records of `pattern` not yet tried on the cond.
+fn untried_combinations(pattern: &[u32], num_tried: usize) -> usize {
+    if pattern.len() < 2 {
+        return 0;
+    }
+    pattern
+        .iter()
+        .map(|&size| LABEL_PATTERN_MAP.with_records(&vec![size], |records| records.len()).unwrap_or(0))
+        .fold(1usize, |n, pool| n.saturating_mul(pool))
+        .saturating_sub(num_tried)
+}
 
 // Reusing mutation
-pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) -> bool {
+pub fn apply_reusing_mutation(handler: &mut SearchHandler, opt: &ReusingOpt) -> bool {
     // 0. 이미 해결된 조건이면 스킵
     if handler.cond.is_done() {
         return false;
     }
 
     // 1. local_stats 전체 백업
+    let start_time = time::Instant::now();
     let snapshot = handler.executor.local_stats.snapshot();
     let buf_backup = handler.buf.clone();
 
@@ -20,38 +163,73 @@ pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) ->
     if pattern.is_empty(){
         return false;
     }
+    // restored at the end, so the records of later rounds keep their own strategy
+    let strategy_backup = mem::replace(&mut handler.executor.strategy, Strategy::Reusing);
 
     // 3. reusing 진행
     let mut execution_count = 0;
-    let map = LABEL_PATTERN_MAP.lock().unwrap();
-    let total_records = if let Some(records) = map.get(&pattern) {
-        records.len()
+    let mut local_exec = 0;
+    let mut local_hits = 0;
+    let mut exact_exec = 0;
+    let mut adapted_exec = 0;
+    // Evicted records may still be in reused_records, so count the untried ones.
+    let untried_records = LABEL_PATTERN_MAP
+        .with_records(&pattern, |records| {
+            records.iter().filter(|r| !handler.cond.reused_records.contains(&r.id)).count()
+        })
+        .unwrap_or(0);
+    let untried_combined = if opt.runs_combined() {
+        untried_combinations(&pattern, handler.cond.reused_combinations.len())
     } else {
         0
     };
-    drop(map);
-
-    if handler.cond.reusing_record_index >= total_records {
-        info!("[Reusing] Pattern {:?}: All records already used (index={}/{}), skipping original reusing",
-              pattern, handler.cond.reusing_record_index, total_records);
-    } else {
+    let iterations = get_iterations(
+        opt.budget,
+        untried_records.saturating_add(untried_combined),
+        handler.cond.fuzz_times,
+    );
+    // Lengths and checksums depend on the rest of the input, so recorded values are not copied.
+    let role = pattern_role(&pattern, handler.cond.base.cmpid);
+    let copies = role.copies_values();
+    let adapts = role.adapts_values();
+
+    if let FieldRole::Length { delta, big_endian } = role {
+        // ===== 1-0단계: 현재 입력 길이로 길이 필드 다시 계산 =====
+        if opt.runs_exact() {
+            let (execs, hits) = try_length_role(handler, delta, big_endian);
+            execution_count += execs;
+            exact_exec += execs;
+            local_exec += execs;
+            local_hits += hits;
+        }
+    } else if !copies {
+        debug!("[Reusing] Pattern {:?} is {:?}, skipping the recorded values", pattern, role);
+    } else if opt.runs_exact() && untried_records == 0 {
+        info!("[Reusing] Pattern {:?}: All records already used ({}), skipping original reusing",
+              pattern, handler.cond.reused_records.len());
+    } else if opt.runs_exact() {
         // ===== 1단계: 동일 패턴 시도 =====
         if let Some(selected_records) = get_next_records(&mut handler.cond, &pattern, iterations) {
             // let actual_iterations = selected_records.len();
-            //    info!("[Reusing] Exact match: pattern={:?}, trying {} records (sequential)", pattern, actual_iterations);
+            //    info!("[Reusing] Exact match: pattern={:?}, trying {} records (ucb)", pattern, actual_iterations);
             
             let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
 
-            for (i, record) in selected_records.iter().enumerate() {
+            for record in selected_records.iter() {
                 if handler.is_stopped_or_skip() {
                     // warn!("[Reusing] Stopped early at iteration {}/{}", i, actual_iterations);
                     break;
                 }
     
                 if insert_critical_value_with_merged(handler, record, &merged_offsets) {
-                    let buf = handler.buf.clone();
-                    handler.execute(&buf);
+                    let outcome = execute_reused(handler);
+                    if outcome.is_hit() {
+                        local_hits += 1;
+                    }
+                    update_record_outcome(&pattern, record.id, &outcome);
                     execution_count += 1;
+                    local_exec += 1;
+                    exact_exec += 1;
                 }
             }
     
@@ -61,12 +239,59 @@ pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) ->
         }
     }
 
+    // ===== 1-2단계: 다른 타겟에서 가져온 동일 패턴 레코드 시도 =====
+    let mut imported_exec = 0;
+    let mut imported_hits = 0;
+    if copies && opt.runs_exact() && execution_count < iterations {
+        let remaining = iterations - execution_count;
+        let (execs, hits) = try_imported_records(handler, &pattern, remaining);
+        execution_count += execs;
+        exact_exec += execs;
+        imported_exec += execs;
+        imported_hits += hits;
+    }
+
+    // ===== 1-3단계: 같은 폭을 다르게 분할한 패턴의 레코드 시도 =====
+    if adapts && opt.runs_adapted() && execution_count < iterations {
+        let remaining = iterations - execution_count;
+        let (execs, hits) = try_repartitioned_records(handler, &pattern, remaining);
+        execution_count += execs;
+        adapted_exec += execs;
+        local_exec += execs;
+        local_hits += hits;
+    }
+
+    // ===== 1-4단계: 폭/엔디언을 변환한 레코드 시도 =====
+    if adapts && opt.runs_adapted() && execution_count < iterations {
+        let remaining = iterations - execution_count;
+        let (execs, hits) = try_adapted_records(handler, &pattern, remaining);
+        execution_count += execs;
+        adapted_exec += execs;
+        local_exec += execs;
+        local_hits += hits;
+    }
+
+    // ===== 1-5단계: 레코드의 원래 오프셋과 그 주변으로 옮겨서 시도 =====
+    let mut relocated_exec = 0;
+    if copies && opt.runs_relocated() && execution_count < iterations {
+        let remaining = iterations - execution_count;
+        let (execs, hits) = try_relocated_records(handler, &pattern, remaining);
+        execution_count += execs;
+        relocated_exec += execs;
+        local_exec += execs;
+        local_hits += hits;
+    }
+
     // ===== 2단계: 남은 횟수를 개별 세그먼트 조합으로 채우기 =====
-    if execution_count < iterations && pattern.len() >= 2 {
+    let mut combined_exec = 0;
+    let mut combined_hits = 0;
+    if opt.runs_combined() && execution_count < iterations && pattern.len() >= 2 {
         let remaining = iterations - execution_count;
         //  info!("[Reusing] Trying combined segments: {} iterations remaining", remaining);
-        let combined_count = try_combined_segments(handler, &pattern, remaining);
-        execution_count += combined_count;
+        let (execs, hits) = try_combined_segments(handler, &pattern, remaining);
+        execution_count += execs;
+        combined_exec += execs;
+        combined_hits += hits;
         //  info!("[Reusing] Combined complete: executed {} iterations", combined_count);
     }
 
@@ -89,6 +314,33 @@ pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) ->
         reusing_stats.num_inputs.0 += inputs_delta;
         reusing_stats.num_hangs.0 += hangs_delta;
         reusing_stats.num_crashes.0 += crashes_delta;
+        reusing_stats.num_local_exec.0 += local_exec;
+        reusing_stats.num_local_hits.0 += local_hits;
+        reusing_stats.num_imported_exec.0 += imported_exec;
+        reusing_stats.num_imported_hits.0 += imported_hits;
+        reusing_stats.num_exact_exec.0 += exact_exec;
+        reusing_stats.num_adapted_exec.0 += adapted_exec;
+        reusing_stats.num_relocated_exec.0 += relocated_exec;
+        reusing_stats.num_combined_exec.0 += combined_exec;
+
+        let solved = handler.cond.is_done();
+        // conds are counted once, on the first round that ran anything
+        let new_cond = execution_count > 0 && !handler.cond.reusing_attempted;
+        if new_cond {
+            handler.cond.reusing_attempted = true;
+            reusing_stats.num_conds.count();
+        }
+        if solved {
+            reusing_stats.num_solved.count();
+        }
+        reusing_stats.time += start_time.elapsed().into();
+        reusing_stats.count_pattern(
+            &pattern,
+            new_cond,
+            execution_count,
+            local_hits + imported_hits + combined_hits,
+            solved,
+        );
 
         // info!("[Reusing] COMPLETE: cmpid={}, pattern={:?}, executed={}/{}, reusing_delta: exec={}, inputs={}, total_reusing: exec={}, inputs={}",
         //       handler.cond.base.cmpid, pattern, execution_count, actual_iterations,
@@ -99,6 +351,7 @@ pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) ->
     // 5. local_stats를 백업으로 복원 (다음 mutation에서 reusing이 카운트 안 되도록)
     handler.executor.local_stats.restore(&snapshot);
     handler.buf = buf_backup;
+    handler.executor.strategy = strategy_backup;
 
     // 복원 후 로그
     // info!("[Reusing] Restored local_stats: exec={}, inputs={}, hangs={}, crashes={}",
@@ -115,91 +368,462 @@ pub fn apply_reusing_mutation(handler: &mut SearchHandler, iterations: usize) ->
     return false;
 }
 
-fn try_combined_segments(handler: &mut SearchHandler, pattern: &Vec<u32>, iterations: usize) -> usize {
-    // 각 세그먼트별로 개별 패턴 레코드 수집
-    let segment_pools: Vec<Vec<Vec<u8>>> = {
-        let map = LABEL_PATTERN_MAP.lock().unwrap();
-
-        pattern.iter().map(|&segment_size| {
-            let single_pattern = vec![segment_size];
-            map.get(&single_pattern)
-                .map(|records| {
-                    records.iter()
-                        .filter_map(|r| r.critical_values.first().cloned())
-                        .collect()
-                })
-                .unwrap_or_default()
-        }).collect()
+// Run the buffer holding the reused values with the cond, and collect what it achieved.
+fn execute_reused(handler: &mut SearchHandler) -> RecordOutcome {
+    let was_done = handler.cond.is_done();
+    let num_crashes = handler.executor.local_stats.num_crashes;
+    // Reused values must not count as the search's steps, e.g. as invariable outputs.
+    handler.execute_cond_copied();
+
+    let mut outcome = RecordOutcome {
+        num_tried: 1,
+        ..Default::default()
     };
+    if handler.executor.has_new_path {
+        outcome.num_new_paths = 1;
+    }
+    if !was_done && handler.cond.is_done() {
+        outcome.num_solved = 1;
+    }
+    if handler.executor.local_stats.num_crashes > num_crashes {
+        outcome.num_crashes = 1;
+    }
+    outcome
+}
 
-    // 모든 세그먼트에 후보가 있는지 확인
-    if segment_pools.iter().any(|pool| pool.is_empty()) {
-        warn!("[Reusing] Cannot combine: some segment pools are empty");
-        return 0;
+// Write the input length into a length field, and the lengths just around it.
+// Returns (executions, hits).
+fn try_length_role(handler: &mut SearchHandler, delta: i64, big_endian: bool) -> (usize, usize) {
+    let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
+    if merged_offsets.len() != 1 {
+        return (0, 0);
     }
-    info!("check!!!!");
+    let width = (merged_offsets[0].end - merged_offsets[0].begin) as usize;
+    let buf_len = handler.buf.len();
+
+    let mut execution_count = 0;
+    let mut hits = 0;
+    for d in [0, 1, -1] {
+        if handler.is_stopped_or_skip() {
+            break;
+        }
+        let value = match encode_length(buf_len, delta + d, big_endian, width) {
+            Some(value) => value,
+            None => continue,
+        };
+        if insert_values(handler, &[value], &merged_offsets) {
+            if execute_reused(handler).is_hit() {
+                hits += 1;
+            }
+            execution_count += 1;
+        }
+    }
+    (execution_count, hits)
+}
+
+// Same as the exact match stage, but the records come from the imported dbs.
+// Returns (executions, hits).
+fn try_imported_records(handler: &mut SearchHandler, pattern: &Vec<u32>, iterations: usize) -> (usize, usize) {
+    let selected_records = match get_next_imported_records(handler.cond, pattern, iterations) {
+        Some(records) => records,
+        None => return (0, 0),
+    };
 
-    // info!("[Reusing] All segment pools available, starting combined mutations");
-    // ✅ 병합 오프셋을 루프 밖에서 1회만 계산
     let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
+    let mut execution_count = 0;
+    let mut hits = 0;
 
-    if merged_offsets.len() != pattern.len() {
-        warn!("[Reusing] Merged offsets mismatch: offsets={}, pattern={}",
-              merged_offsets.len(), pattern.len());
-        return 0;
+    for (source, record) in selected_records.iter() {
+        if handler.is_stopped_or_skip() {
+            break;
+        }
+
+        if insert_critical_value_with_merged(handler, record, &merged_offsets) {
+            if execute_reused(handler).is_hit() {
+                hits += 1;
+                debug!("[Reusing] Imported record (cmpid={}) from {} hit on cmpid={}",
+                       record.cmpid, get_imported_source(*source), handler.cond.base.cmpid);
+            }
+            execution_count += 1;
+        }
     }
 
-    // ✅ 최대 버퍼 크기 미리 계산 및 할당
-    let max_end = merged_offsets.iter()
-        .map(|s| s.end as usize)
-        .max()
-        .unwrap_or(0);
+    (execution_count, hits)
+}
 
-    if max_end > handler.buf.len() {
-        handler.buf.resize(max_end, 0);
+// How a stored value was adapted to the target slot.
+#[derive(Clone, Copy)]
+enum Adaptation {
+    ResizeLittle = 0,
+    ResizeBig = 1,
+    Swap = 2,
+    Repartition = 3,
+}
+
+// A stored record adapted to the shape of the target cond.
+struct AdaptedCandidate {
+    key: u64,
+    source_pattern: Vec<u32>,
+    record_id: u64,
+    values: Vec<Vec<u8>>,
+}
+
+impl AdaptedCandidate {
+    fn new(source_pattern: &[u32], record: &CondRecord, adaptation: Adaptation, values: Vec<Vec<u8>>) -> Self {
+        Self {
+            key: (record.id << 8) | adaptation as u64,
+            source_pattern: source_pattern.to_vec(),
+            record_id: record.id,
+            values,
+        }
     }
+}
 
-    let mut rng = rand::thread_rng();
-    let mut execution_count = 0;
+// Collect at most `n` untried candidates for a single slot pattern from records of other widths,
+// extended or truncated in both endians, and byte-swapped records of its own width.
+fn collect_adapted_candidates(
+    handler: &SearchHandler,
+    pattern: &[u32],
+    merged_offsets: &[TagSeg],
+    n: usize,
+) -> Vec<AdaptedCandidate> {
+    let mut candidates = vec![];
+    if pattern.len() != 1 {
+        return candidates;
+    }
 
-    // ✅ Vec 재사용 (매번 할당 X)
-    let mut combined_values: Vec<Vec<u8>> = Vec::with_capacity(pattern.len());
+    let tried = &handler.cond.reused_adapted;
+    let width = pattern[0];
+    let signed = merged_offsets[0].sign;
+    for &src_width in ADAPT_WIDTHS.iter() {
+        let src_pattern = vec![src_width];
+        // the candidates are picked under the lock, so only their values are copied
+        let full = LABEL_PATTERN_MAP.with_records(&src_pattern, |records| {
+            for record in records {
+                let value = match record.critical_values.first() {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let mut adapted = vec![];
+                if src_width == width {
+                    let swapped = swap_bytes(value);
+                    if swapped != *value {
+                        adapted.push((Adaptation::Swap, swapped));
+                    }
+                } else {
+                    let little = adapt_value(value, width as usize, signed, Endian::Little);
+                    let big = adapt_value(value, width as usize, signed, Endian::Big);
+                    if big != little {
+                        adapted.push((Adaptation::ResizeBig, big));
+                    }
+                    adapted.push((Adaptation::ResizeLittle, little));
+                }
 
-    for iter in 0..iterations {
-        if handler.is_stopped_or_skip() {
-            warn!("[Reusing] Stopped early at combined iteration {}/{}", iter, iterations);
+                for (adaptation, v) in adapted {
+                    let candidate = AdaptedCandidate::new(&src_pattern, record, adaptation, vec![v]);
+                    if !tried.contains(&candidate.key) {
+                        candidates.push(candidate);
+                        if candidates.len() >= n {
+                            return true;
+                        }
+                    }
+                }
+            }
+            false
+        });
+        if full == Some(true) {
             break;
         }
+    }
+
+    candidates
+}
+
+// Collect at most `n` untried candidates from the records of patterns with the same total width,
+// whose values are re-partitioned at the boundaries of the pattern.
+fn collect_repartitioned_candidates(handler: &SearchHandler, pattern: &Vec<u32>, n: usize) -> Vec<AdaptedCandidate> {
+    let src_patterns = find_repartition_patterns(pattern);
+    let tried = &handler.cond.reused_adapted;
+    let mut candidates = vec![];
+
+    for src_pattern in src_patterns {
+        let full = LABEL_PATTERN_MAP.with_records(&src_pattern, |records| {
+            for record in records {
+                let values = match split_value(&record.critical_values.concat(), pattern) {
+                    Some(values) => values,
+                    None => continue,
+                };
+                let candidate = AdaptedCandidate::new(&src_pattern, record, Adaptation::Repartition, values);
+                if !tried.contains(&candidate.key) {
+                    candidates.push(candidate);
+                    if candidates.len() >= n {
+                        return true;
+                    }
+                }
+            }
+            false
+        });
+        if full == Some(true) {
+            break;
+        }
+    }
+
+    candidates
+}
+
+// Reuse records whose layout differs from the target but covers the same bytes.
+// Returns (executions, hits).
+fn try_repartitioned_records(handler: &mut SearchHandler, pattern: &Vec<u32>, iterations: usize) -> (usize, usize) {
+    let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
+    if merged_offsets.len() != pattern.len() {
+        return (0, 0);
+    }
+
+    let candidates = collect_repartitioned_candidates(handler, pattern, iterations);
+    try_candidates(handler, candidates, &merged_offsets)
+}
+
+fn try_candidates(
+    handler: &mut SearchHandler,
+    candidates: Vec<AdaptedCandidate>,
+    merged_offsets: &[TagSeg],
+) -> (usize, usize) {
+    let mut execution_count = 0;
+    let mut hits = 0;
 
-        combined_values.clear();
+    for candidate in candidates {
+        if handler.is_stopped_or_skip() {
+            break;
+        }
 
-        // 각 세그먼트별로 랜덤 선택
-        for pool in &segment_pools {
-            if let Some(record) = pool.choose(&mut rng) {
-                combined_values.push(record.clone());
+        handler.cond.reused_adapted.insert(candidate.key);
+        if insert_values(handler, &candidate.values, merged_offsets) {
+            let outcome = execute_reused(handler);
+            if outcome.is_hit() {
+                hits += 1;
             }
+            update_record_outcome(&candidate.source_pattern, candidate.record_id, &outcome);
+            execution_count += 1;
         }
+    }
 
+    (execution_count, hits)
+}
 
+// Reuse records whose width differs from the target, after adapting their values.
+// Returns (executions, hits).
+fn try_adapted_records(handler: &mut SearchHandler, pattern: &[u32], iterations: usize) -> (usize, usize) {
+    let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
+    if merged_offsets.len() != pattern.len() {
+        return (0, 0);
+    }
 
-        // 조합된 값으로 mutation
-        if combined_values.len() == merged_offsets.len() {
-            // 값 삽입
-            for (seg, value) in merged_offsets.iter().zip(combined_values.iter()) {
-                let begin = seg.begin as usize;
-                let end = seg.end as usize;
-                let copy_len = value.len().min(end - begin);
+    let candidates = collect_adapted_candidates(handler, pattern, &merged_offsets, iterations);
+    try_candidates(handler, candidates, &merged_offsets)
+}
 
-                handler.buf[begin..begin + copy_len]
-                    .copy_from_slice(&value[..copy_len]);
+// Shifts to try from the original offsets of a record: the ones that hit before,
+// then the original offsets, then nearer shifts first.
+fn relocation_shifts(hit_shifts: &[i32]) -> Vec<i32> {
+    let mut shifts = hit_shifts.to_vec();
+    shifts.push(0);
+    for d in 1..=config::MAX_RELOCATE_SHIFT {
+        shifts.push(d);
+        shifts.push(-d);
+    }
+    let mut seen = HashSet::new();
+    shifts.retain(|s| seen.insert(*s));
+    shifts
+}
+
+// Move the segments by `shift` bytes, if they still fit in the input.
+fn relocate_segments(segments: &[TagSeg], shift: i32, buf_len: usize) -> Option<Vec<TagSeg>> {
+    segments
+        .iter()
+        .map(|seg| {
+            let begin = seg.begin as i64 + shift as i64;
+            let end = seg.end as i64 + shift as i64;
+            if begin < 0 || end > buf_len as i64 {
+                return None;
+            }
+            Some(TagSeg {
+                sign: seg.sign,
+                begin: begin as u32,
+                end: end as u32,
+            })
+        })
+        .collect()
+}
+
+// (record id, values, shift, relocated offsets)
+type Relocation = (u64, Vec<Vec<u8>>, i32, Vec<TagSeg>);
+
+// Write the records of the pattern at their own offsets, shifted, instead of the cond's offsets,
+// for inputs whose fields have moved. Shifts that hit are credited to the record.
+// Returns (executions, hits).
+fn try_relocated_records(handler: &mut SearchHandler, pattern: &Vec<u32>, iterations: usize) -> (usize, usize) {
+    let target_offsets = merge_continuous_segments(&handler.cond.offsets);
+    let base_buf = handler.buf.clone();
+    let tried = &handler.cond.reused_relocations;
+
+    // picked under the lock, so only their values are copied
+    let candidates: Vec<Relocation> = LABEL_PATTERN_MAP
+        .with_records(pattern, |records| {
+            // records with known shifts first, then the most productive
+            let mut records: Vec<&CondRecord> = records.iter().collect();
+            records.sort_by(|a, b| {
+                a.hit_shifts.is_empty().cmp(&b.hit_shifts.is_empty()).then(
+                    b.outcome
+                        .mean_reward()
+                        .partial_cmp(&a.outcome.mean_reward())
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+            });
+            let per_record: Vec<(Vec<TagSeg>, Vec<i32>)> = records
+                .iter()
+                .map(|r| (merge_continuous_segments(&r.offsets), relocation_shifts(&r.hit_shifts)))
+                .collect();
+
+            // The same distance for every record before going further away.
+            let mut candidates = vec![];
+            let max_level = per_record.iter().map(|(_, shifts)| shifts.len()).max().unwrap_or(0);
+            for level in 0..max_level {
+                for (record, (segments, shifts)) in records.iter().zip(per_record.iter()) {
+                    let shift = match shifts.get(level) {
+                        Some(&shift) => shift,
+                        None => continue,
+                    };
+                    if segments.len() != record.critical_values.len() || tried.contains(&(record.id, shift)) {
+                        continue;
+                    }
+                    let relocated = match relocate_segments(segments, shift, base_buf.len()) {
+                        Some(relocated) => relocated,
+                        None => continue,
+                    };
+                    // the cond's own offsets are tried by the exact stage
+                    if relocated == target_offsets {
+                        continue;
+                    }
+                    candidates.push((record.id, record.critical_values.clone(), shift, relocated));
+                    if candidates.len() >= iterations {
+                        return candidates;
+                    }
+                }
             }
+            candidates
+        })
+        .unwrap_or_default();
+
+    let mut execution_count = 0;
+    let mut hits = 0;
+    for (id, values, shift, relocated) in candidates {
+        if handler.is_stopped_or_skip() {
+            break;
+        }
 
-            let buf = handler.buf.clone();
-            handler.execute(&buf);
+        handler.cond.reused_relocations.insert((id, shift));
+        handler.buf.clone_from(&base_buf);
+        if insert_values(handler, &values, &relocated) {
+            let outcome = execute_reused(handler);
+            if outcome.is_hit() {
+                hits += 1;
+                credit_record_shift(pattern, id, shift);
+            }
+            update_record_outcome(pattern, id, &outcome);
             execution_count += 1;
         }
     }
-    execution_count
+    handler.buf = base_buf;
+
+    (execution_count, hits)
+}
+
+// Records of a single segment pattern, most productive first.
+fn segment_pool(segment_size: u32) -> Vec<(u64, Vec<u8>)> {
+    let mut pool: Vec<(f64, u64, Vec<u8>)> = LABEL_PATTERN_MAP
+        .with_records(&vec![segment_size], |records| {
+            records
+                .iter()
+                .filter_map(|r| {
+                    r.critical_values
+                        .first()
+                        .map(|v| (r.outcome.mean_reward(), r.id, v.clone()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Stable sort: equally productive records keep their insertion order.
+    pool.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    pool.into_iter().map(|(_, id, v)| (id, v)).collect()
+}
+
+// Combine the records of the single segments in the order of `Combinations`,
+// skipping the combinations already tried on the cond in earlier rounds.
+fn try_combined_segments(handler: &mut SearchHandler, pattern: &Vec<u32>, iterations: usize) -> (usize, usize) {
+    // 각 세그먼트별로 개별 패턴 레코드 수집
+    let segment_pools: Vec<Vec<(u64, Vec<u8>)>> = pattern.iter().map(|&size| segment_pool(size)).collect();
+
+    // 모든 세그먼트에 후보가 있는지 확인
+    if segment_pools.iter().any(|pool| pool.is_empty()) {
+        warn!("[Reusing] Cannot combine: some segment pools are empty");
+        return (0, 0);
+    }
+
+    // ✅ 병합 오프셋을 루프 밖에서 1회만 계산
+    let merged_offsets = merge_continuous_segments(&handler.cond.offsets);
+
+    if merged_offsets.len() != pattern.len() {
+        warn!("[Reusing] Merged offsets mismatch: offsets={}, pattern={}",
+              merged_offsets.len(), pattern.len());
+        return (0, 0);
+    }
+
+    // The pools may have changed since the last round, so combinations are remembered by their values.
+    let pool_sizes: Vec<usize> = segment_pools.iter().map(|pool| pool.len()).collect();
+    let mut candidates = vec![];
+    for indexes in Combinations::new(&pool_sizes) {
+        let values: Vec<Vec<u8>> = indexes
+            .iter()
+            .zip(segment_pools.iter())
+            .map(|(&i, pool)| pool[i].1.clone())
+            .collect();
+        let key = hash_values(&values);
+        if handler.cond.reused_combinations.contains(&key) {
+            continue;
+        }
+        candidates.push((indexes, values, key));
+        if candidates.len() >= iterations {
+            break;
+        }
+    }
+
+    if candidates.is_empty() {
+        info!("[Reusing] Pattern {:?}: all {} combinations already tried",
+              pattern, handler.cond.reused_combinations.len());
+        return (0, 0);
+    }
+
+    let mut execution_count = 0;
+    let mut hits = 0;
+    for (indexes, values, key) in candidates {
+        if handler.is_stopped_or_skip() {
+            break;
+        }
+
+        handler.cond.reused_combinations.insert(key);
+        if insert_values(handler, &values, &merged_offsets) {
+            let outcome = execute_reused(handler);
+            if outcome.is_hit() {
+                hits += 1;
+            }
+            // credit every segment record of the combination
+            for ((&i, pool), &size) in indexes.iter().zip(segment_pools.iter()).zip(pattern.iter()) {
+                update_record_outcome(&vec![size], pool[i].0, &outcome);
+            }
+            execution_count += 1;
+        }
+    }
+    (execution_count, hits)
 }
 
 fn insert_critical_value_with_merged(
@@ -207,8 +831,14 @@ fn insert_critical_value_with_merged(
     record: &CondRecord,
     merged_offsets: &[TagSeg],
 ) -> bool {
-    let critical_values = &record.critical_values;
+    insert_values(handler, &record.critical_values, merged_offsets)
+}
 
+fn insert_values(
+    handler: &mut SearchHandler,
+    critical_values: &[Vec<u8>],
+    merged_offsets: &[TagSeg],
+) -> bool {
     if merged_offsets.len() != critical_values.len() {
         return false;
     }
@@ -250,4 +880,40 @@ fn merge_continuous_segments(offsets: &Vec<TagSeg>) -> Vec<TagSeg> {
     }
     merged.push(current);
     merged
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_iterations() {
+        // no stats yet: the prior alone gives the max budget
+        assert_eq!(adaptive_iterations(1000, 0, 0, 1), config::MAX_REUSING_ITERATIONS);
+        // bounded by the records available
+        assert_eq!(adaptive_iterations(30, 0, 0, 1), 30);
+        assert_eq!(adaptive_iterations(0, 0, 0, 1), config::MIN_REUSING_ITERATIONS);
+        // reusing rarely works
+        assert_eq!(adaptive_iterations(1000, 10000, 0, 1), config::MIN_REUSING_ITERATIONS);
+        // a cond fuzzed many times gets less
+        assert_eq!(adaptive_iterations(1000, 0, 0, 4), config::MAX_REUSING_ITERATIONS / 4);
+        assert_eq!(adaptive_iterations(1000, 0, 0, 100), config::MIN_REUSING_ITERATIONS);
+    }
+
+    #[test]
+    fn test_relocation() {
+        let shifts = relocation_shifts(&[3, 0]);
+        assert_eq!(&shifts[..4], &[3, 0, 1, -1]);
+        assert_eq!(shifts.len(), 2 * config::MAX_RELOCATE_SHIFT as usize + 1);
+
+        let segments = vec![
+            TagSeg { sign: false, begin: 2, end: 4 },
+            TagSeg { sign: false, begin: 6, end: 7 },
+        ];
+        let moved = relocate_segments(&segments, -2, 8).unwrap();
+        assert_eq!((moved[0].begin, moved[0].end, moved[1].begin, moved[1].end), (0, 2, 4, 5));
+        assert!(relocate_segments(&segments, -3, 8).is_none());
+        assert!(relocate_segments(&segments, 1, 8).is_some());
+        assert!(relocate_segments(&segments, 2, 8).is_none());
+    }
+}
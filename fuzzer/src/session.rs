@@ -0,0 +1,169 @@
+// Checkpoints of a fuzzing session, so that it can be resumed without re-tracking its inputs.
+use crate::{
+    branches::GlobalBranches,
+    cond_stmt::CondStmt,
+    depot::{self, Depot},
+    stats::{ChartCounters, ChartStats},
+};
+use angora_common::defs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::Path,
+    sync::{atomic::Ordering, RwLock},
+};
+
+const SESSION_FILE: &str = "session.json";
+
+#[derive(Serialize, Deserialize)]
+struct SessionState {
+    num_inputs: usize,
+    num_hangs: usize,
+    num_crashes: usize,
+    counters: ChartCounters,
+    queue: Vec<(CondStmt, usize)>,
+}
+
+// Save the queue, the bitmaps and the counters into the session dir of `out_dir`.
+// The pattern map is checkpointed next to them, in label_patterns.json.
+pub fn save_session(
+    out_dir: &Path,
+    depot: &Depot,
+    global_branches: &GlobalBranches,
+    stats: &RwLock<ChartStats>,
+) -> io::Result<()> {
+    let dir = out_dir.join(defs::SESSION_DIR);
+    fs::create_dir_all(&dir)?;
+
+    // The bitmaps go first, so every path they hold has its conds in the queue.
+    // Paths found in between are only found again after resuming.
+    global_branches.save(&dir)?;
+    let state = SessionState {
+        num_inputs: depot.num_inputs.load(Ordering::Relaxed),
+        num_hangs: depot.num_hangs.load(Ordering::Relaxed),
+        num_crashes: depot.num_crashes.load(Ordering::Relaxed),
+        counters: stats.read().unwrap().counters(),
+        queue: depot.queue_entries(),
+    };
+
+    let path = dir.join(SESSION_FILE);
+    depot::write_atomically(&path, |writer| Ok(serde_json::to_writer(writer, &state)?))?;
+
+    debug!("[Session] Checkpointed {} conds to {:?}", state.queue.len(), dir);
+    Ok(())
+}
+
+fn count_files(dir: &Path) -> io::Result<usize> {
+    Ok(fs::read_dir(dir)?.count())
+}
+
+// Restore the pattern map checkpointed next to the session, keeping the record ids the
+// conds of the queue refer to. It goes before any other db, whose records take fresh ids.
+pub fn load_session_patterns(out_dir: &Path) -> io::Result<()> {
+    let pattern_db = out_dir.join(defs::LABEL_PATTERN_DB_FILE);
+    if pattern_db.exists() {
+        depot::resume_from_db(&pattern_db)?;
+    }
+    Ok(())
+}
+
+// Restore the state saved by `save_session`, after `load_session_patterns`.
+// Returns the number of conds in the queue.
+pub fn load_session(
+    out_dir: &Path,
+    depot: &Depot,
+    global_branches: &GlobalBranches,
+    stats: &RwLock<ChartStats>,
+) -> io::Result<usize> {
+    let dir = out_dir.join(defs::SESSION_DIR);
+    let state: SessionState = serde_json::from_reader(BufReader::new(File::open(dir.join(SESSION_FILE))?))?;
+    global_branches.load(&dir)?;
+
+    // Files saved after the checkpoint are kept, new ones are numbered after them.
+    let dirs = &depot.dirs;
+    depot.num_inputs.store(state.num_inputs.max(count_files(&dirs.inputs_dir)?), Ordering::Relaxed);
+    depot.num_hangs.store(state.num_hangs.max(count_files(&dirs.hangs_dir)?), Ordering::Relaxed);
+    depot.num_crashes.store(state.num_crashes.max(count_files(&dirs.crashes_dir)?), Ordering::Relaxed);
+    stats.write().unwrap().restore_counters(&state.counters);
+
+    let num_conds = state.queue.len();
+    depot.restore_queue(state.queue);
+    info!("[Session] Resumed {} conds from {:?}", num_conds, dir);
+    Ok(num_conds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fuzz_type::FUZZ_TYPE_NUM, stats::REUSING_STATS};
+    use std::env;
+
+    #[test]
+    fn test_session_round_trip() {
+        // the bitmaps are built on the stack in debug builds
+        std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(session_round_trip)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    fn session_round_trip() {
+        let pid = unsafe { libc::getpid() as usize };
+        let out_dir = env::temp_dir().join(format!("angora_session_test_{}", pid));
+        let _ = fs::remove_dir_all(&out_dir);
+        let seeds_dir = out_dir.join("seeds");
+
+        let depot = Depot::new(seeds_dir.clone(), &out_dir, Default::default());
+        let mut cond = CondStmt::new();
+        cond.base.cmpid = 7;
+        cond.fuzz_times = 2;
+        depot.restore_queue(vec![(cond, 3)]);
+        depot.num_inputs.store(5, Ordering::Relaxed);
+        let global_branches = GlobalBranches::new();
+        let stats = RwLock::new(ChartStats::new());
+        let counters: ChartCounters = serde_json::from_value(serde_json::json!({
+            "elapsed": {"secs": 60, "nanos": 0},
+            "track_time": {"secs": 1, "nanos": 0},
+            "num_rounds": 1,
+            "num_exec": 42,
+            "num_inputs": 5,
+            "num_hangs": 0,
+            "num_crashes": 0,
+            "avg_exec_time": [250.0, 4],
+            "avg_edge_num": [30.0, 4],
+            "fuzz": vec![serde_json::json!({"num_exec": 40, "time": {"secs": 50, "nanos": 0}}); FUZZ_TYPE_NUM],
+            "reusing": {"num_exec": 30, "num_local_exec": 20, "num_local_hits": 3},
+        }))
+        .unwrap();
+        stats.write().unwrap().restore_counters(&counters);
+        save_session(&out_dir, &depot, &global_branches, &stats).unwrap();
+        *REUSING_STATS.lock().unwrap() = Default::default();
+
+        let resumed = Depot::new(seeds_dir, &out_dir, Default::default());
+        let resumed_stats = RwLock::new(ChartStats::new());
+        load_session_patterns(&out_dir).unwrap();
+        let num_conds = load_session(&out_dir, &resumed, &GlobalBranches::new(), &resumed_stats).unwrap();
+        assert_eq!(num_conds, 1);
+        let queue = resumed.queue_entries();
+        assert_eq!((queue[0].0.base.cmpid, queue[0].0.fuzz_times, queue[0].1), (7, 2, 3));
+        assert_eq!(resumed.num_inputs.load(Ordering::Relaxed), 5);
+        let counters = serde_json::to_value(resumed_stats.read().unwrap().counters()).unwrap();
+        assert_eq!(counters["num_exec"], 42);
+        assert_eq!(counters["avg_exec_time"], serde_json::json!([250.0, 4]));
+        assert_eq!(counters["avg_edge_num"], serde_json::json!([30.0, 4]));
+        assert_eq!(counters["fuzz"][0]["num_exec"], 40);
+        assert_eq!(counters["fuzz"][0]["time"]["secs"], 50);
+        assert_eq!(counters["reusing"]["num_exec"], 30);
+        let reusing = REUSING_STATS.lock().unwrap();
+        assert_eq!((reusing.num_local_exec.0, reusing.num_local_hits.0), (20, 3));
+        drop(reusing);
+
+        // the depots dump their queues into the directory when dropped
+        drop(depot);
+        drop(resumed);
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
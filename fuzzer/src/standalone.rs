@@ -0,0 +1,65 @@
+// An executor for the tools that run a target outside of a fuzzing session (cmin, tmin).
+use crate::{branches, command, depot, executor::Executor, search, stats};
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+pub struct Standalone {
+    pub executor: Executor,
+    command_option: command::CommandOpt,
+    work_dir: PathBuf,
+}
+
+impl Standalone {
+    // The executor needs a depot and a tmp dir, which are kept in a work dir of their own.
+    // Without `track_target`, the fast binary stands in for it and must not be tracked.
+    pub fn new(track_target: Option<&str>, pargs: Vec<String>, mem_limit: u64, time_limit: u64) -> Self {
+        let pid = unsafe { libc::getpid() as usize };
+        let work_dir = env::temp_dir().join(format!("angora_standalone_{}", pid));
+        fs::create_dir_all(&work_dir).unwrap();
+
+        let track_target = track_target.map(str::to_owned).unwrap_or_else(|| pargs[0].clone());
+        let command_option = command::CommandOpt::new(
+            "llvm",
+            &track_target,
+            pargs,
+            &work_dir,
+            "gd",
+            mem_limit,
+            time_limit,
+            false,
+            false,
+            search::ReusingOpt::default(),
+        );
+        let depot = Arc::new(depot::Depot::new(
+            work_dir.clone(),
+            &work_dir,
+            depot::SchedulePolicy::default(),
+        ));
+        let global_branches = Arc::new(branches::GlobalBranches::new());
+        let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
+        let executor = Executor::new(command_option.specify(0), global_branches, depot, stats);
+
+        Self {
+            executor,
+            command_option,
+            work_dir,
+        }
+    }
+
+    // Stop the forkserver and remove the work dir.
+    pub fn close(self) {
+        let Self {
+            executor,
+            command_option,
+            work_dir,
+        } = self;
+        drop(executor);
+        drop(command_option);
+        if let Err(e) = fs::remove_dir_all(&work_dir) {
+            warn!("Could not remove {:?}: {:?}", work_dir, e);
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use super::*;
 use crate::{branches::GlobalBranches, depot::Depot};
 use colored::*;
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 use std::sync::Arc;
 use crate::stats::REUSING_STATS;
+use crate::search::ReusingOpt;
 
 #[derive(Default, Serialize)]
 pub struct ChartStats {
@@ -23,17 +24,75 @@ pub struct ChartStats {
     num_hangs: Counter,
     num_crashes: Counter,
 
+    cache_hits: Counter,
+    cache_misses: Counter,
+
     fuzz: FuzzStats,
+    reusing_opt: ReusingOpt,
     reusing: ReusingStats,
     search: SearchStats,
     state: StateStats,
 }
 
+// Counters of the chart that are not recomputed from the queue, kept in session checkpoints.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ChartCounters {
+    elapsed: time::Duration,
+    track_time: time::Duration,
+    num_rounds: usize,
+    num_exec: usize,
+    num_inputs: usize,
+    num_hangs: usize,
+    num_crashes: usize,
+    avg_exec_time: Average,
+    avg_edge_num: Average,
+    fuzz: FuzzStats,
+    reusing: ReusingStats,
+}
+
 impl ChartStats {
     pub fn new() -> Self {
         Default::default()
     }
 
+    pub fn counters(&self) -> ChartCounters {
+        ChartCounters {
+            elapsed: self.init_time.0.elapsed(),
+            track_time: self.track_time.0,
+            num_rounds: self.num_rounds.0,
+            num_exec: self.num_exec.0,
+            num_inputs: self.num_inputs.0,
+            num_hangs: self.num_hangs.0,
+            num_crashes: self.num_crashes.0,
+            avg_exec_time: self.avg_exec_time,
+            avg_edge_num: self.avg_edge_num,
+            fuzz: self.fuzz.clone(),
+            reusing: REUSING_STATS.lock().unwrap().clone(),
+        }
+    }
+
+    // The time of the resumed session goes on from where it stopped.
+    pub fn restore_counters(&mut self, counters: &ChartCounters) {
+        let now = time::Instant::now();
+        self.init_time = TimeIns(now.checked_sub(counters.elapsed).unwrap_or(now));
+        self.track_time = counters.track_time.into();
+        self.num_rounds = counters.num_rounds.into();
+        self.num_exec = counters.num_exec.into();
+        self.num_inputs = counters.num_inputs.into();
+        self.num_hangs = counters.num_hangs.into();
+        self.num_crashes = counters.num_crashes.into();
+        self.avg_exec_time = counters.avg_exec_time;
+        self.avg_edge_num = counters.avg_edge_num;
+        self.fuzz = counters.fuzz.clone();
+        // the adaptive reusing budget goes on from the success rate so far
+        *REUSING_STATS.lock().unwrap() = counters.reusing.clone();
+        self.reusing = counters.reusing.clone();
+    }
+
+    pub fn set_reusing_opt(&mut self, opt: ReusingOpt) {
+        self.reusing_opt = opt;
+    }
+
     pub fn sync_from_local(&mut self, local: &mut LocalStats) {
         self.track_time += local.track_time;
         self.num_rounds.count();
@@ -63,6 +122,9 @@ impl ChartStats {
         self.iter_pq(depot);
         self.sync_from_branches(gb);
         self.sync_reusing_stats();
+        let (hits, misses) = depot.input_cache_stats();
+        self.cache_hits = hits.into();
+        self.cache_misses = misses.into();
     }
 
     fn sync_reusing_stats(&mut self) {
@@ -156,6 +218,7 @@ impl fmt::Display for ChartStats {
     EXECS  |   TOTAL: {},     ROUND: {},     MAX_R: {}
     SPEED  |  PERIOD: {:6}r/s    TIME: {}us, 
     FOUND  |    PATH: {},     HANGS: {},   CRASHES: {}
+    CACHE  |     HIT: {},      MISS: {}
 {}
 {}
    REUSING | {}
@@ -179,6 +242,8 @@ impl fmt::Display for ChartStats {
             self.num_inputs,
             self.num_hangs,
             self.num_crashes,
+            self.cache_hits,
+            self.cache_misses,
             " -- FUZZ -- ".blue().bold(),
             self.fuzz,
             self.reusing,
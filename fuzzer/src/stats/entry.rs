@@ -1,9 +1,10 @@
 use super::*;
 use colored::*;
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
 #[derive(
     Default, Clone, Copy, Eq, PartialEq, Add, AddAssign, From, Into, Ord, PartialOrd, Serialize,
+    Deserialize,
 )]
 pub struct Counter(pub usize);
 
@@ -19,7 +20,7 @@ impl fmt::Display for Counter {
     }
 }
 
-#[derive(Clone, Copy, Constructor, Serialize)]
+#[derive(Clone, Copy, Constructor, Serialize, Deserialize)]
 pub struct Average(pub f32, usize);
 
 impl Default for Average {
@@ -102,7 +103,7 @@ impl SyncAverage {
     }
 }
 
-#[derive(Default, Clone, Copy, Add, AddAssign, From, Into, Serialize)]
+#[derive(Default, Clone, Copy, Add, AddAssign, From, Into, Serialize, Deserialize)]
 pub struct TimeDuration(pub time::Duration);
 
 impl fmt::Display for TimeDuration {
@@ -1,8 +1,9 @@
 use super::*;
 use crate::cond_stmt::CondStmt;
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Default, Serialize)]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StrategyStats {
     pub time: TimeDuration,
     pub num_conds: Counter,
@@ -27,7 +28,7 @@ impl fmt::Display for StrategyStats {
     }
 }
 
-#[derive(Clone, Default, Serialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct FuzzStats([StrategyStats; fuzz_type::FUZZ_TYPE_NUM]);
 
 impl FuzzStats {
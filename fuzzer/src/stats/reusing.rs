@@ -1,37 +1,88 @@
 use super::*;
-use serde_derive::Serialize;
-use std::sync::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Mutex};
 
-#[derive(Clone, Default, Serialize)]
+// How reusing worked on the conds of one label pattern.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternHits {
+    pub num_conds: Counter,
+    pub num_solved: Counter,
+    pub num_exec: Counter,
+    pub num_hits: Counter,
+    pub hit_rate: f32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ReusingStats {
+    pub num_conds: Counter,
+    pub num_solved: Counter,
+    pub time: TimeDuration,
     pub num_exec: Counter,
     pub num_inputs: Counter,
     pub num_hangs: Counter,
     pub num_crashes: Counter,
+    // executions of the exact (local and imported), adapted, relocated and combined stages
+    pub num_exact_exec: Counter,
+    pub num_adapted_exec: Counter,
+    pub num_relocated_exec: Counter,
+    pub num_combined_exec: Counter,
+    // hits: executions that found a new path or solved the cond
+    pub num_local_exec: Counter,
+    pub num_local_hits: Counter,
+    pub num_imported_exec: Counter,
+    pub num_imported_hits: Counter,
+    // label pattern -> hits, keyed by its text form for json
+    pub patterns: BTreeMap<String, PatternHits>,
 }
 
 impl ReusingStats {
     pub fn new() -> Self {
         Default::default()
     }
+
+    // `new_cond` is set on the first round of reusing that ran on the cond.
+    pub fn count_pattern(&mut self, pattern: &[u32], new_cond: bool, num_exec: usize, num_hits: usize, solved: bool) {
+        let hits = self.patterns.entry(format!("{:?}", pattern)).or_default();
+        if new_cond {
+            hits.num_conds.count();
+        }
+        if solved {
+            hits.num_solved.count();
+        }
+        hits.num_exec.0 += num_exec;
+        hits.num_hits.0 += num_hits;
+        if hits.num_exec.0 > 0 {
+            hits.hit_rate = hits.num_hits.0 as f32 / hits.num_exec.0 as f32;
+        }
+    }
 }
 
 impl fmt::Display for ReusingStats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let zero_counter = Counter(0);
         write!(
             f,
-            "CONDS: {}, EXEC: {}, TIME: {}, FOUND: {} - {} - {}",
-            zero_counter,  // reusing은 CONDS 0
+            "CONDS: {}, EXEC: {}, TIME: {}, FOUND: {} - {} - {}\n           | SOLVED: {}, EXACT: {}, ADAPTED: {}, RELOCATED: {}, COMBINED: {}\n           | HITS | LOCAL: {} / {}, IMPORTED: {} / {}",
+            self.num_conds,
             self.num_exec,
-            "[--:--:--]",  // TIME은 추적 안 함
+            self.time,
             self.num_inputs,
             self.num_hangs,
             self.num_crashes,
+            self.num_solved,
+            self.num_exact_exec,
+            self.num_adapted_exec,
+            self.num_relocated_exec,
+            self.num_combined_exec,
+            self.num_local_hits,
+            self.num_local_exec,
+            self.num_imported_hits,
+            self.num_imported_exec,
         )
     }
 }
 
 lazy_static::lazy_static! {
     pub static ref REUSING_STATS: Mutex<ReusingStats> = Mutex::new(ReusingStats::new());
-}
\ No newline at end of file
+}
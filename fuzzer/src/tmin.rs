@@ -0,0 +1,245 @@
+// Test case minimization, like afl-tmin: shrink an input while it still crashes or hangs the same way.
+use crate::{executor::StatusType, standalone::Standalone};
+use angora_common::tag::TagSeg;
+use std::{collections::BTreeSet, fs};
+
+// Block deletion starts with blocks of about 1/16 of the input.
+const TMIN_SET_STEPS: usize = 16;
+// Rounds of deletion, while they keep shrinking the input.
+const TMIN_MAX_ROUNDS: usize = 8;
+const NORMAL_BYTE: u8 = b'0';
+
+pub fn taint_mask(len: usize, offsets: &[TagSeg]) -> Vec<bool> {
+    let mut mask = vec![false; len];
+    for seg in offsets {
+        let end = (seg.end as usize).min(len);
+        for tainted in mask.iter_mut().take(end).skip(seg.begin as usize) {
+            *tainted = true;
+        }
+    }
+    mask
+}
+
+// Maximal runs of untainted bytes, as (begin, end).
+fn untainted_runs(mask: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs = vec![];
+    let mut begin = None;
+    for (i, &tainted) in mask.iter().enumerate() {
+        match (tainted, begin) {
+            (false, None) => begin = Some(i),
+            (true, Some(b)) => {
+                runs.push((b, i));
+                begin = None;
+            },
+            _ => {},
+        }
+    }
+    if let Some(b) = begin {
+        runs.push((b, mask.len()));
+    }
+    runs
+}
+
+// The input and its taint mask, edited together.
+struct Minimizer<F> {
+    buf: Vec<u8>,
+    mask: Vec<bool>,
+    check: F,
+}
+
+impl<F> Minimizer<F>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    fn try_delete(&mut self, begin: usize, end: usize) -> bool {
+        let mut buf = Vec::with_capacity(self.buf.len() - (end - begin));
+        buf.extend_from_slice(&self.buf[..begin]);
+        buf.extend_from_slice(&self.buf[end..]);
+        if buf.is_empty() || !(self.check)(&buf) {
+            return false;
+        }
+        self.buf = buf;
+        self.mask.drain(begin..end);
+        true
+    }
+
+    // Set the bytes at `offsets` to NORMAL_BYTE at once.
+    fn try_normalize(&mut self, offsets: &[usize]) -> bool {
+        let mut buf = self.buf.clone();
+        for &i in offsets {
+            buf[i] = NORMAL_BYTE;
+        }
+        if buf == self.buf || !(self.check)(&buf) {
+            return false;
+        }
+        self.buf = buf;
+        true
+    }
+
+    // The bytes no cond depends on go first, a whole run at a time.
+    // From the end, so that the runs before stay in place.
+    fn delete_untainted(&mut self) {
+        for (begin, end) in untainted_runs(&self.mask).into_iter().rev() {
+            self.try_delete(begin, end);
+        }
+    }
+
+    fn delete_blocks(&mut self) {
+        let mut del_len = (self.buf.len() / TMIN_SET_STEPS).max(1).next_power_of_two();
+        loop {
+            let mut pos = 0;
+            while pos < self.buf.len() {
+                let end = (pos + del_len).min(self.buf.len());
+                if !self.try_delete(pos, end) {
+                    pos = end;
+                }
+            }
+            if del_len == 1 {
+                break;
+            }
+            del_len /= 2;
+        }
+    }
+
+    fn normalize(&mut self) {
+        let untainted: Vec<usize> = (0..self.buf.len()).filter(|&i| !self.mask[i]).collect();
+        self.try_normalize(&untainted);
+
+        let values: BTreeSet<u8> = self.buf.iter().copied().filter(|&b| b != NORMAL_BYTE).collect();
+        for v in values {
+            let offsets: Vec<usize> = (0..self.buf.len()).filter(|&i| self.buf[i] == v).collect();
+            self.try_normalize(&offsets);
+        }
+
+        let mut offsets: Vec<usize> = (0..self.buf.len()).filter(|&i| self.buf[i] != NORMAL_BYTE).collect();
+        offsets.sort_by_key(|&i| self.mask[i]);
+        for i in offsets {
+            self.try_normalize(&[i]);
+        }
+    }
+}
+
+// Shrink `buf` by deleting blocks and normalizing bytes, while `check` holds.
+// `mask` marks the tainted bytes, which are deleted last.
+pub fn minimize<F>(buf: Vec<u8>, mask: Vec<bool>, check: F) -> Vec<u8>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    assert_eq!(buf.len(), mask.len());
+    let mut m = Minimizer { buf, mask, check };
+    for _ in 0..TMIN_MAX_ROUNDS {
+        let len = m.buf.len();
+        m.delete_untainted();
+        m.delete_blocks();
+        if m.buf.len() == len {
+            break;
+        }
+    }
+    m.normalize();
+    m.buf
+}
+
+pub fn tmin_main(
+    in_file: &str,
+    out_file: &str,
+    track_target: Option<&str>,
+    keep_trace: bool,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: u64,
+) {
+    pretty_env_logger::init();
+
+    let buf = fs::read(in_file).expect("Could not read the input file!");
+    if buf.is_empty() {
+        error!("FATAL: {} is empty", in_file);
+        panic!();
+    }
+    let mut standalone = Standalone::new(track_target, pargs, mem_limit, time_limit);
+    let executor = &mut standalone.executor;
+
+    let (status, trace, _) = executor.run_path(&buf);
+    if status != StatusType::Crash && status != StatusType::Timeout {
+        error!("FATAL: {} neither crashes nor hangs ({:?}), nothing to minimize", in_file, status);
+        standalone.close();
+        panic!();
+    }
+    // a hang ends at no fixed point, so its trace is not compared
+    let keep_trace = keep_trace && status != StatusType::Timeout;
+
+    let mask = if track_target.is_some() {
+        taint_mask(buf.len(), &executor.tainted_offsets(&buf))
+    } else {
+        vec![true; buf.len()]
+    };
+    let num_tainted = mask.iter().filter(|&&t| t).count();
+
+    let mut num_execs = 0;
+    let orig_len = buf.len();
+    let min_buf = minimize(buf, mask, |b| {
+        num_execs += 1;
+        let (s, t, _) = executor.run_path(b);
+        s == status && (!keep_trace || t == trace)
+    });
+    fs::write(out_file, &min_buf).expect("Could not write the output file!");
+
+    println!(
+        "Minimized {} ({:?}, {} tainted bytes) from {} to {} bytes in {} execs, saved to {}",
+        in_file,
+        status,
+        num_tainted,
+        orig_len,
+        min_buf.len(),
+        num_execs,
+        out_file
+    );
+    standalone.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taint_mask() {
+        let offsets = vec![
+            TagSeg {
+                sign: false,
+                begin: 1,
+                end: 3,
+            },
+            TagSeg {
+                sign: false,
+                begin: 5,
+                end: 9,
+            },
+        ];
+        let mask = taint_mask(6, &offsets);
+        assert_eq!(mask, vec![false, true, true, false, false, true]);
+        assert_eq!(untainted_runs(&mask), vec![(0, 1), (3, 5)]);
+        assert!(untainted_runs(&[true; 3]).is_empty());
+    }
+
+    #[test]
+    fn test_minimize() {
+        // crashes on "BUG", tracked at 50..53
+        let mut buf = vec![b'x'; 100];
+        buf[50..53].copy_from_slice(b"BUG");
+        let mask = taint_mask(100, &[TagSeg { sign: false, begin: 50, end: 53 }]);
+        let mut num_execs = 0;
+        let min_buf = minimize(buf.clone(), mask, |b| {
+            num_execs += 1;
+            twoway::find_bytes(b, b"BUG").is_some()
+        });
+        assert_eq!(min_buf, b"BUG");
+        // the untainted runs are deleted at once
+        assert!(num_execs < 20, "{}", num_execs);
+
+        // without taint, blocks are deleted blindly, to the same result
+        let min_buf = minimize(buf, vec![true; 100], |b| twoway::find_bytes(b, b"BUG").is_some());
+        assert_eq!(min_buf, b"BUG");
+
+        let min_buf = minimize(b"Zabcdefg".to_vec(), vec![false; 8], |b| b.len() >= 4 && b[0] == b'Z');
+        assert_eq!(min_buf, b"Z000");
+    }
+}
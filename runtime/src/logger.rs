@@ -1,14 +1,32 @@
-use bincode::{deserialize_from, serialize_into};
-use std::{collections::HashMap, env, fs, io, path::Path};
+use bincode::{deserialize_from, serialize, serialize_into};
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    os::unix::io::AsRawFd,
+    path::Path,
+    ptr,
+    sync::atomic::{AtomicI32, AtomicPtr, Ordering},
+};
 
 use crate::{len_label, tag_set_wrap};
 use angora_common::{cond_stmt_base::CondStmtBase, config, defs, log_data::LogData};
 
+// A snapshot of the log serialized ahead of time, and the fd of the track file, so that the
+// crash handler only has to write(2) them. It can not serialize, allocate or lock.
+static CRASH_SNAPSHOT: AtomicPtr<Vec<u8>> = AtomicPtr::new(ptr::null_mut());
+static CRASH_FD: AtomicI32 = AtomicI32::new(-1);
+// A new snapshot is taken when the conds grow by 1/SNAPSHOT_RATIO, so a crash loses at most that much.
+const SNAPSHOT_RATIO: usize = 8;
+
 #[derive(Debug)]
 pub struct Logger {
     data: LogData,
     fd: Option<fs::File>,
     order_map: HashMap<(u32, u32), u32>,
+    // snapshots are only taken when tracking a crashing input
+    crash_snapshots: bool,
+    // number of conds in the last snapshot
+    snapshot_len: usize,
 }
 
 impl Logger {
@@ -22,10 +40,39 @@ impl Logger {
             Err(_) => None,
         };
 
+        let crash_snapshots = fd.is_some() && env::var(defs::TRACK_CRASH_VAR).is_ok();
+        if crash_snapshots {
+            CRASH_FD.store(fd.as_ref().unwrap().as_raw_fd(), Ordering::SeqCst);
+        }
+
         Self {
             data: LogData::new(),
             fd,
             order_map: HashMap::new(),
+            crash_snapshots,
+            snapshot_len: 0,
+        }
+    }
+
+    pub fn tracks_crash(&self) -> bool {
+        self.crash_snapshots
+    }
+
+    // Called after a cond and its magic bytes are saved.
+    pub fn snapshot(&mut self) {
+        let len = self.data.cond_list.len();
+        if !self.crash_snapshots || len - self.snapshot_len < (self.snapshot_len / SNAPSHOT_RATIO).max(1) {
+            return;
+        }
+        self.snapshot_len = len;
+        let buf = match serialize(&self.data) {
+            Ok(buf) => buf,
+            Err(_) => return,
+        };
+        // the handler sees either the old snapshot or the new one, both complete
+        let old = CRASH_SNAPSHOT.swap(Box::into_raw(Box::new(buf)), Ordering::SeqCst);
+        if !old.is_null() {
+            drop(unsafe { Box::from_raw(old) });
         }
     }
 
@@ -82,6 +129,8 @@ impl Logger {
     }
 
     fn fini(&self) {
+        // a crash from here on leaves the file as it is
+        CRASH_FD.store(-1, Ordering::SeqCst);
         if let Some(fd) = &self.fd {
             let mut writer = io::BufWriter::new(fd);
             serialize_into(&mut writer, &self.data).expect("Could not serialize data.");
@@ -95,6 +144,31 @@ impl Drop for Logger {
     }
 }
 
+// Write the last snapshot of the log into the track file, from a signal handler.
+// Only async-signal-safe calls.
+pub fn write_crash_snapshot() {
+    let fd = CRASH_FD.load(Ordering::SeqCst);
+    let snapshot = CRASH_SNAPSHOT.load(Ordering::SeqCst);
+    if fd < 0 || snapshot.is_null() {
+        return;
+    }
+    let buf = unsafe { &*snapshot };
+    let mut written = 0;
+    while written < buf.len() {
+        let n = unsafe {
+            libc::write(
+                fd,
+                buf[written..].as_ptr() as *const libc::c_void,
+                buf.len() - written,
+            )
+        };
+        if n <= 0 {
+            break;
+        }
+        written += n as usize;
+    }
+}
+
 pub fn get_log_data(path: &Path) -> io::Result<LogData> {
     let f = fs::File::open(path)?;
     if f.metadata().unwrap().len() == 0 {
@@ -3,11 +3,48 @@ use crate::tag_set_wrap;
 use angora_common::{cond_stmt_base::*, defs};
 use lazy_static::lazy_static;
 use libc;
-use std::{slice, sync::Mutex};
+use std::{mem, ptr, slice, sync::Mutex};
 
 // use shm_conds;
 lazy_static! {
-    static ref LC: Mutex<Option<Logger>> = Mutex::new(Some(Logger::new()));
+    static ref LC: Mutex<Option<Logger>> = {
+        let logger = Logger::new();
+        if logger.tracks_crash() {
+            install_crash_handler();
+        }
+        Mutex::new(Some(logger))
+    };
+}
+
+const CRASH_SIGNALS: [libc::c_int; 5] = [libc::SIGSEGV, libc::SIGBUS, libc::SIGABRT, libc::SIGFPE, libc::SIGILL];
+
+// If the program crashes, write the last snapshot of the log, so that the fuzzer
+// can still use the conds reached by a crashing input. Then crash with the default action,
+// which SA_RESETHAND has put back.
+// The crash may be inside malloc or with LC locked, so nothing but write(2) is called.
+extern "C" fn flush_on_crash(sig: libc::c_int) {
+    logger::write_crash_snapshot();
+    unsafe {
+        libc::raise(sig);
+    }
+}
+
+// Only the signals the program leaves at their default action are taken, so the handlers
+// of the program itself, ASan or a runtime are kept as they are.
+fn install_crash_handler() {
+    for &sig in &CRASH_SIGNALS {
+        unsafe {
+            let mut old: libc::sigaction = mem::zeroed();
+            if libc::sigaction(sig, ptr::null(), &mut old) != 0 || old.sa_sigaction != libc::SIG_DFL {
+                continue;
+            }
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = flush_on_crash as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESETHAND;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(sig, &action, ptr::null_mut());
+        }
+    }
 }
 
 fn infer_eq_sign(op: u32, lb1: u32, lb2: u32) -> u32 {
@@ -138,6 +175,7 @@ pub extern "C" fn __dfsw___angora_trace_switch_tt(
             }
             lc.save(cond_i);
         }
+        lc.snapshot();
     }
 }
 
@@ -201,6 +239,7 @@ pub extern "C" fn __dfsw___angora_trace_fn_tt(
     if let Some(ref mut lc) = *lcl {
         lc.save(cond);
         lc.save_magic_bytes((arg1, arg2));
+        lc.snapshot();
     }
 }
 
@@ -259,6 +298,7 @@ fn log_cmp(
     let mut lcl = LC.lock().expect("Could not lock LC.");
     if let Some(ref mut lc) = *lcl {
         lc.save(cond);
+        lc.snapshot();
     }
 }
 